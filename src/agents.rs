@@ -0,0 +1,145 @@
+//! Java agents - resolve `java_agents` config into `-javaagent:` JVM arguments.
+//!
+//! Local agents are used in place; manifest agents are downloaded once into
+//! `dist/agents/<id>-<version>.jar` and verified against the manifest checksum.
+
+use crate::config::{Config, JavaAgent};
+use crate::download::{calculate_sha256, download_file};
+use crate::error::KarateError;
+use crate::manifest::{fetch_manifest, ReleasesManifest};
+use crate::platform::KaratePaths;
+use anyhow::Result;
+use console::style;
+use std::path::PathBuf;
+
+/// The JAR an agent is loaded from. Manifest agents may not be downloaded yet.
+pub fn agent_jar(agent: &JavaAgent, paths: &KaratePaths) -> Result<PathBuf> {
+    match (&agent.path, &agent.id, &agent.version) {
+        (Some(path), None, _) => Ok(PathBuf::from(path)),
+        (None, Some(id), Some(version)) => Ok(paths
+            .dist
+            .join("agents")
+            .join(format!("{id}-{version}.jar"))),
+        _ => Err(KarateError::Config(
+            "java_agents entries need either 'path' or 'id' with 'version'".to_string(),
+        )
+        .into()),
+    }
+}
+
+/// `-javaagent:` arguments for all configured agents, in config order.
+pub fn agent_args(config: &Config, paths: &KaratePaths) -> Result<Vec<String>> {
+    config
+        .java_agents
+        .iter()
+        .map(|agent| {
+            let jar = agent_jar(agent, paths)?;
+            let mut arg = format!("-javaagent:{}", jar.to_string_lossy());
+            if let Some(options) = &agent.options {
+                arg.push('=');
+                arg.push_str(options);
+            }
+            Ok(arg)
+        })
+        .collect()
+}
+
+/// Download missing manifest agents and verify checksums of local ones.
+pub async fn ensure_agents(config: &Config, paths: &KaratePaths) -> Result<()> {
+    let mut manifest: Option<ReleasesManifest> = None;
+
+    for agent in &config.java_agents {
+        let jar = agent_jar(agent, paths)?;
+
+        if agent.path.is_some() {
+            if !jar.exists() {
+                return Err(KarateError::Config(format!(
+                    "Java agent not found: {}",
+                    jar.display()
+                ))
+                .into());
+            }
+            if let Some(expected) = &agent.sha256 {
+                let actual = calculate_sha256(&jar)?;
+                if !actual.eq_ignore_ascii_case(expected) {
+                    return Err(KarateError::ChecksumMismatch {
+                        file: jar.to_string_lossy().to_string(),
+                        expected: expected.clone(),
+                        actual,
+                    }
+                    .into());
+                }
+            }
+            continue;
+        }
+
+        if jar.exists() {
+            continue;
+        }
+
+        // Only fetched when an agent actually needs downloading
+        if manifest.is_none() {
+            manifest = Some(fetch_manifest().await?);
+        }
+        let (id, version) = (
+            agent.id.as_deref().unwrap_or_default(),
+            agent.version.as_deref().unwrap_or_default(),
+        );
+        let (url, sha256) = manifest
+            .as_ref()
+            .and_then(|m| m.get_jar_download(id, version))
+            .ok_or_else(|| {
+                KarateError::Config(format!(
+                    "Java agent {id} {version} is not in the release manifest"
+                ))
+            })?;
+
+        eprintln!(
+            "{} Downloading Java agent {} {}...",
+            style("▶").cyan().bold(),
+            id,
+            version
+        );
+        download_file(url, &jar, Some(sha256)).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agent_args_keep_config_order_and_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = KaratePaths::in_home(dir.path());
+        let config = Config {
+            java_agents: vec![
+                JavaAgent {
+                    id: Some("jacoco".into()),
+                    version: Some("0.8.12".into()),
+                    options: Some("destfile=target/jacoco.exec".into()),
+                    ..JavaAgent::default()
+                },
+                JavaAgent {
+                    path: Some("/opt/agents/trace.jar".into()),
+                    ..JavaAgent::default()
+                },
+            ],
+            ..Config::default()
+        };
+
+        let jacoco = paths.dist.join("agents").join("jacoco-0.8.12.jar");
+        assert_eq!(
+            agent_args(&config, &paths).unwrap(),
+            vec![
+                format!(
+                    "-javaagent:{}=destfile=target/jacoco.exec",
+                    jacoco.to_string_lossy()
+                ),
+                "-javaagent:/opt/agents/trace.jar".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! AppCDS - class-data sharing archives for faster JVM startup.
+//!
+//! An archive is created by a warm-up run with `-XX:ArchiveClassesAtExit` and stored
+//! in `cache/cds/<paths>-<contents>.jsa`. Both parts of the name hash the java
+//! executable, the JRE's module image and every classpath entry: `<paths>` by path
+//! only, `<contents>` by path, size and modification time. Replacing the JAR, the JRE
+//! or an ext JAR selects a different archive, and the one it replaces (same paths,
+//! other contents) is removed once the new one exists.
+
+use crate::config::{load_merged_config, ConfigSelection};
+use crate::delegate::{plan_launch, ClasspathEntry};
+use crate::platform::KaratePaths;
+use crate::process::{run_supervised, Outcome, Supervision};
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+/// Longest a warm-up run may take before it's abandoned.
+const WARMUP_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Feature executed by the warm-up run, so engine classes end up in the archive.
+const WARMUP_FEATURE: &str = "Feature: CDS warm-up
+
+Scenario: warm-up
+* def warmup = { ready: true }
+* match warmup == { ready: true }
+";
+
+/// JVM options that already control class-data sharing.
+const CDS_FLAGS: &[&str] = &[
+    "-Xshare",
+    "-XX:SharedArchiveFile",
+    "-XX:ArchiveClassesAtExit",
+];
+
+/// Directory holding CDS archives.
+pub fn cds_dir(paths: &KaratePaths) -> PathBuf {
+    paths.cache.join("cds")
+}
+
+/// Archive path for a java executable and classpath (whether or not it exists).
+pub fn archive_path(paths: &KaratePaths, java: &Path, classpath: &[ClasspathEntry]) -> PathBuf {
+    let mut names = Sha256::new();
+    let mut contents = Sha256::new();
+    let mut add = |path: &Path| {
+        names.update(path.to_string_lossy().as_bytes());
+        names.update([0]);
+        contents.update(fingerprint(path).as_bytes());
+        contents.update([0]);
+    };
+    add(java);
+    // The module image changes with every JRE update, even in place
+    if let Some(home) = java.parent().and_then(|bin| bin.parent()) {
+        add(&home.join("lib").join("modules"));
+    }
+    for entry in classpath {
+        add(Path::new(&entry.path));
+    }
+    let names = &hex::encode(names.finalize())[..8];
+    let contents = &hex::encode(contents.finalize())[..16];
+    cds_dir(paths).join(format!("{names}-{contents}.jsa"))
+}
+
+/// The `-XX:SharedArchiveFile` argument for a launch, if a matching archive exists
+/// and the JVM options don't already configure class-data sharing.
+pub fn shared_archive_arg(
+    paths: &KaratePaths,
+    java: &Path,
+    classpath: &[ClasspathEntry],
+    jvm_args: &[String],
+) -> Option<String> {
+    if jvm_args
+        .iter()
+        .any(|arg| CDS_FLAGS.iter().any(|flag| arg.starts_with(flag)))
+    {
+        return None;
+    }
+    let archive = archive_path(paths, java, classpath);
+    archive
+        .is_file()
+        .then(|| format!("-XX:SharedArchiveFile={}", archive.display()))
+}
+
+/// Path, size and modification time of a file.
+fn fingerprint(path: &Path) -> String {
+    let (len, modified) = std::fs::metadata(path)
+        .map(|m| {
+            let modified = m
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or_default();
+            (m.len(), modified)
+        })
+        .unwrap_or_default();
+    format!("{}|{len}|{modified}", path.display())
+}
+
+/// Create the archive for the current config with a warm-up run, unless it exists.
+/// Returns the archive path, or `None` when CDS is disabled or the warm-up failed.
+pub async fn generate(selection: &ConfigSelection) -> Result<Option<PathBuf>> {
+    let paths = KaratePaths::new();
+    let config = load_merged_config(selection)?;
+    if config.cds == Some(false) {
+        return Ok(None);
+    }
+
+    let mut plan = plan_launch(&config, &paths, &[], &[])?;
+    let archive = archive_path(&paths, &plan.java, &plan.classpath);
+    if archive.is_file() {
+        return Ok(Some(archive));
+    }
+
+    // Concurrent setup/update runs each warm up in their own directory and partial
+    // archive; the last rename wins
+    let pid = std::process::id();
+    let warmup_dir = cds_dir(&paths).join(format!("warmup-{pid}"));
+    std::fs::create_dir_all(&warmup_dir)?;
+    let feature = warmup_dir.join("warmup.feature");
+    std::fs::write(&feature, WARMUP_FEATURE)?;
+
+    let partial = archive.with_extension(format!("{pid}.jsa.tmp"));
+    plan.jvm_args
+        .retain(|arg| !CDS_FLAGS.iter().any(|flag| arg.starts_with(flag)));
+    plan.jvm_args
+        .push(format!("-XX:ArchiveClassesAtExit={}", partial.display()));
+    plan.args = vec!["run".to_string(), feature.to_string_lossy().to_string()];
+    let (command_args, argfile) = plan.command_args(&paths.cache);
+    if let Some(argfile) = &argfile {
+        argfile.write()?;
+    }
+
+    // Reports and logs from the warm-up stay in its own directory
+    let mut cmd = tokio::process::Command::new(&plan.java);
+    cmd.args(&command_args)
+        .current_dir(&warmup_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let supervision = Supervision {
+        timeout: Some(WARMUP_TIMEOUT),
+        ..Supervision::default()
+    };
+    let outcome = run_supervised(cmd, &supervision).await;
+    let _ = std::fs::remove_dir_all(&warmup_dir);
+
+    // The archive is written at JVM exit whatever the test result was
+    let created = matches!(outcome?, Outcome::Exited(_))
+        && std::fs::metadata(&partial)
+            .map(|m| m.len() > 0)
+            .unwrap_or(false);
+    if !created {
+        let _ = std::fs::remove_file(&partial);
+        return Ok(None);
+    }
+    std::fs::rename(&partial, &archive)?;
+    prune(&archive);
+    Ok(Some(archive))
+}
+
+/// Remove archives for the same paths made before their contents changed, and partial
+/// archives left behind by warm-ups that never finished.
+fn prune(archive: &Path) {
+    let (Some(dir), Some(name)) = (archive.parent(), archive.file_name()) else {
+        return;
+    };
+    let name = name.to_string_lossy();
+    let Some((names, _)) = name.split_once('-') else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let stale = if file_name.ends_with(".jsa.tmp") {
+            entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age > WARMUP_TIMEOUT * 2)
+        } else {
+            file_name.ends_with(".jsa")
+                && file_name.starts_with(&format!("{names}-"))
+                && path != archive
+        };
+        if stale {
+            debug!("Removing stale CDS archive {}", path.display());
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delegate::ClasspathSource;
+
+    #[test]
+    fn archive_changes_with_classpath_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = KaratePaths::in_home(dir.path());
+        let java = dir.path().join("bin").join("java");
+        let ext = dir.path().join("ext.jar");
+        std::fs::write(&ext, b"v1").unwrap();
+        let classpath = vec![ClasspathEntry {
+            path: ext.to_string_lossy().to_string(),
+            source: ClasspathSource::GlobalExt,
+        }];
+
+        let first = archive_path(&paths, &java, &classpath);
+        assert_eq!(first, archive_path(&paths, &java, &classpath));
+        std::fs::write(&ext, b"v2-longer").unwrap();
+        assert_ne!(first, archive_path(&paths, &java, &classpath));
+        assert_ne!(first, archive_path(&paths, &java, &[]));
+    }
+
+    #[test]
+    fn prunes_archives_replaced_by_new_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let cds = dir.path();
+        for name in [
+            "aaaa-old.jsa",
+            "aaaa-new.jsa",
+            "bbbb-other.jsa",
+            "aaaa-new.1.jsa.tmp",
+        ] {
+            std::fs::write(cds.join(name), b"x").unwrap();
+        }
+        prune(&cds.join("aaaa-new.jsa"));
+
+        let mut left: Vec<String> = std::fs::read_dir(cds)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        // A fresh partial archive may belong to a warm-up that is still running
+        assert_eq!(
+            left,
+            vec!["aaaa-new.1.jsa.tmp", "aaaa-new.jsa", "bbbb-other.jsa"]
+        );
+    }
+
+    #[test]
+    fn explicit_sharing_options_win() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = KaratePaths::in_home(dir.path());
+        let java = Path::new("/no/such/java");
+        let jvm_args = vec!["-Xshare:off".to_string()];
+        assert_eq!(shared_archive_arg(&paths, java, &[], &jvm_args), None);
+    }
+}
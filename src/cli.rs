@@ -1,6 +1,9 @@
 //! CLI argument parsing using clap derive macros.
 
-use clap::{Args, Parser, Subcommand};
+use crate::process::parse_duration;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Karate CLI - setup and launcher for the Karate automation framework
 #[derive(Parser, Debug)]
@@ -19,10 +22,70 @@ pub struct Cli {
     #[arg(long = "cp", global = true, num_args = 1)]
     pub extra_classpath: Vec<String>,
 
+    /// Dotenv file with environment variables for delegated commands.
+    /// Can be specified multiple times; loaded after any `env_files` from config.
+    #[arg(long = "env-file", global = true, num_args = 1)]
+    pub env_files: Vec<String>,
+
+    /// Print the resolved java command (java, JVM options, classpath with sources)
+    /// instead of launching it. Only applies to JAR-delegated commands.
+    /// Use --dry-run=json for machine-readable output.
+    #[arg(
+        long,
+        visible_alias = "print-command",
+        global = true,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    pub dry_run: Option<OutputFormat>,
+
+    /// Start the JVM with a JDWP agent listening on localhost (default port 5005)
+    /// so a debugger can attach. Only applies to JAR-delegated commands.
+    #[arg(
+        long,
+        global = true,
+        value_name = "PORT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "5005"
+    )]
+    pub debug_jvm: Option<u16>,
+
+    /// With --debug-jvm, wait for a debugger to attach before running
+    #[arg(long, global = true, requires = "debug_jvm")]
+    pub debug_suspend: bool,
+
+    /// Stop a delegated command after this long (e.g. 90s, 30m, 1h): SIGTERM, then
+    /// SIGKILL after a grace period. Exits with code 5.
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Also write the delegated command's stdout and stderr to this file
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Config profile to apply on top of the resolved config (from the `profiles` map)
+    #[arg(long, global = true, env = "KARATE_PROFILE")]
+    pub profile: Option<String>,
+
+    /// How to print launcher errors on stderr: text, or one JSON object with
+    /// code, kind, message and hint
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    pub error_format: OutputFormat,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
+/// Output format for commands with optional machine-readable output.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// First-run setup wizard
@@ -46,6 +109,28 @@ pub enum Command {
     /// Show version information
     Version(VersionArgs),
 
+    /// Run java from the managed JRE (same as `karate jre exec java`)
+    #[command(disable_help_flag = true)]
+    Java(JavaArgs),
+
+    /// Print the resolved java, Karate JAR and classpath (for IDE integration)
+    Classpath(ClasspathArgs),
+
+    /// Print shell exports (JAVA_HOME, KARATE_JAR, ...) for `eval "$(karate env)"`
+    Env(EnvArgs),
+
+    /// Rerun the failed scenarios from the last run's reports
+    RerunFailed(RerunFailedArgs),
+
+    /// Rerun feature files as they change
+    Watch(WatchArgs),
+
+    /// List recent delegated commands, or replay one
+    History(HistoryArgs),
+
+    /// Preview how features split across CI shards (--shard-index/--shard-total)
+    Shard(ShardArgs),
+
     /// Pass-through to Karate JAR (run, mock, mcp, init, etc.)
     #[command(external_subcommand)]
     External(Vec<String>),
@@ -111,13 +196,22 @@ pub struct ConfigArgs {
     #[arg(long, conflicts_with = "local")]
     pub global: bool,
 
-    /// Edit project config (./.karate/karate-cli.json)
+    /// Edit project config (.karate/karate-cli.json in the project root)
     #[arg(long, conflicts_with = "global")]
     pub local: bool,
 
     /// Print resolved (merged) config as JSON
     #[arg(long)]
     pub show: bool,
+
+    #[command(subcommand)]
+    pub subcommand: Option<ConfigSubcommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigSubcommand {
+    /// Print the JSON Schema for karate-cli.json (for editor autocompletion)
+    Schema,
 }
 
 // ============================================================================
@@ -137,6 +231,138 @@ pub enum JreSubcommand {
 
     /// Check JRE health and compatibility
     Doctor,
+
+    /// Run a tool (keytool, jcmd, ...) from the managed JRE's bin/ directory
+    Exec(JreExecArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct JreExecArgs {
+    /// Tool name, e.g. java, keytool, jcmd
+    pub tool: String,
+
+    /// Arguments passed to the tool (after --)
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct JavaArgs {
+    /// Arguments passed to java
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub args: Vec<String>,
+}
+
+// ============================================================================
+// Rerun-failed command
+// ============================================================================
+
+#[derive(Args, Debug)]
+pub struct RerunFailedArgs {
+    /// Directory with Karate's JSON or JUnit reports from the previous run
+    #[arg(long, default_value = "target/karate-reports")]
+    pub reports_dir: PathBuf,
+
+    /// Rerun still-failing scenarios up to this many times
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub retries: u32,
+
+    /// Extra arguments for `karate run` (after --)
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub args: Vec<String>,
+}
+
+// ============================================================================
+// Watch command
+// ============================================================================
+
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    /// Directories or files to watch
+    #[arg(default_value = ".")]
+    pub paths: Vec<String>,
+
+    /// Quiet period before a batch of changes starts a run (e.g. 300ms, 1s)
+    #[arg(long, default_value = "300ms", value_parser = parse_duration)]
+    pub debounce: Duration,
+
+    /// Don't clear the screen between runs
+    #[arg(long)]
+    pub no_clear: bool,
+
+    /// Extra arguments for `karate run` (after --)
+    #[arg(last = true, allow_hyphen_values = true)]
+    pub args: Vec<String>,
+}
+
+// ============================================================================
+// History command
+// ============================================================================
+
+#[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct HistoryArgs {
+    #[command(subcommand)]
+    pub subcommand: Option<HistorySubcommand>,
+
+    /// Only show failed or timed-out commands
+    #[arg(long)]
+    pub failed: bool,
+
+    /// Number of entries to show
+    #[arg(long, short = 'n', default_value_t = 20)]
+    pub limit: usize,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistorySubcommand {
+    /// Run a recorded command again, from its original directory
+    Rerun(HistoryRerunArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryRerunArgs {
+    /// Entry id from `karate history`
+    pub id: u64,
+}
+
+// ============================================================================
+// Shard command
+// ============================================================================
+
+#[derive(Args, Debug)]
+pub struct ShardArgs {
+    #[command(subcommand)]
+    pub subcommand: ShardSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ShardSubcommand {
+    /// Show which features each shard runs
+    Plan(ShardPlanArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ShardPlanArgs {
+    /// Feature files or directories, as passed to `karate run`
+    #[arg(required = true)]
+    pub paths: Vec<String>,
+
+    /// Number of shards
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub shard_total: u64,
+
+    /// Only show this shard (from 1)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub shard_index: Option<u64>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 // ============================================================================
@@ -184,6 +410,36 @@ pub struct DoctorArgs {
     pub json: bool,
 }
 
+// ============================================================================
+// Classpath command
+// ============================================================================
+
+#[derive(Args, Debug)]
+pub struct ClasspathArgs {
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+// ============================================================================
+// Env command
+// ============================================================================
+
+#[derive(Args, Debug)]
+pub struct EnvArgs {
+    /// Shell syntax to emit (default: detected from $SHELL)
+    #[arg(long, value_enum)]
+    pub shell: Option<Shell>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
 // ============================================================================
 // Version command
 // ============================================================================
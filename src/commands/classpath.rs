@@ -0,0 +1,66 @@
+//! Classpath command - print the resolved java, Karate JAR and classpath for IDEs.
+
+use crate::cli::ClasspathArgs;
+use crate::config::load_merged_config;
+use crate::delegate::{plan_launch, ClasspathEntry, LaunchOptions};
+use crate::error::ExitCode;
+use crate::jre::java_home;
+use crate::platform::KaratePaths;
+use anyhow::Result;
+use console::style;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct ClasspathInfo {
+    java: String,
+    java_home: Option<String>,
+    jar: String,
+    main_class: String,
+    jvm_args: Vec<String>,
+    classpath: Vec<ClasspathEntry>,
+    /// Entries joined with the platform separator, ready for `-cp`
+    classpath_string: String,
+}
+
+pub async fn run(args: ClasspathArgs, options: &LaunchOptions) -> Result<ExitCode> {
+    let paths = KaratePaths::new();
+    let config = load_merged_config(&options.config)?;
+
+    // Same resolution as a delegated run, minus the command-specific parts
+    let plan = plan_launch(&config, &paths, &[], &options.extra_classpath)?;
+
+    let info = ClasspathInfo {
+        java: plan.java.to_string_lossy().to_string(),
+        java_home: java_home(&plan.java),
+        jar: plan.jar.to_string_lossy().to_string(),
+        main_class: plan.main_class.clone(),
+        jvm_args: plan.jvm_args.clone(),
+        classpath_string: plan.classpath_string(),
+        classpath: plan.classpath,
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(ExitCode::Success);
+    }
+
+    println!("{} Resolved Classpath", style("▶").cyan().bold());
+    println!();
+    println!("  Java:       {}", style(&info.java).green());
+    println!("  JAR:        {}", style(&info.jar).green());
+    println!("  Main class: {}", info.main_class);
+    if !info.jvm_args.is_empty() {
+        println!("  JVM opts:   {}", info.jvm_args.join(" "));
+    }
+    println!();
+    println!("{}", style("Classpath").bold().underlined());
+    for entry in &info.classpath {
+        println!(
+            "  {} {}",
+            entry.path,
+            style(format!("({})", entry.source)).dim()
+        );
+    }
+
+    Ok(ExitCode::Success)
+}
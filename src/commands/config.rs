@@ -1,15 +1,19 @@
 //! Config command - view and edit configuration.
 
-use crate::cli::ConfigArgs;
-use crate::config::load_merged_config;
+use crate::cli::{ConfigArgs, ConfigSubcommand};
+use crate::config::{config_schema, load_merged_config, ConfigSelection};
 use crate::error::ExitCode;
 use crate::platform::KaratePaths;
 use anyhow::Result;
 use console::style;
 
-pub async fn run(args: ConfigArgs) -> Result<ExitCode> {
+pub async fn run(args: ConfigArgs, selection: &ConfigSelection) -> Result<ExitCode> {
+    if let Some(ConfigSubcommand::Schema) = args.subcommand {
+        return show_schema().await;
+    }
+
     if args.show {
-        return show_config().await;
+        return show_config(selection).await;
     }
 
     let config_path = if args.local {
@@ -48,11 +52,18 @@ pub async fn run(args: ConfigArgs) -> Result<ExitCode> {
 }
 
 /// Show the resolved (merged) configuration.
-async fn show_config() -> Result<ExitCode> {
-    let config = load_merged_config()?;
-    let json = serde_json::to_string_pretty(&config)?;
+async fn show_config(selection: &ConfigSelection) -> Result<ExitCode> {
+    let config = load_merged_config(selection)?;
+    let json = serde_json::to_string_pretty(&config.redacted())?;
 
     println!("{}", json);
 
     Ok(ExitCode::Success)
 }
+
+/// Print the JSON Schema for karate-cli.json.
+async fn show_schema() -> Result<ExitCode> {
+    println!("{}", serde_json::to_string_pretty(&config_schema())?);
+
+    Ok(ExitCode::Success)
+}
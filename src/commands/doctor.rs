@@ -1,23 +1,32 @@
 //! Doctor command - full system diagnostics.
 
 use crate::cli::DoctorArgs;
+use crate::config::{
+    load_merged_config, validate_config_files, ConfigIssue, ConfigSelection, ExitCodeMode,
+};
+use crate::env::{resolve_env, EnvVar, REDACTED};
 use crate::error::ExitCode;
 use crate::jre::{find_active_jre, find_system_jre, MIN_JAVA_VERSION};
 use crate::platform::{KaratePaths, Platform};
+use crate::resources::JvmResources;
 use anyhow::Result;
 use console::style;
 use serde::Serialize;
+use std::collections::BTreeMap;
 
 #[derive(Serialize)]
 struct DoctorReport {
     platform: PlatformInfo,
     karate_home: String,
+    project_root: Option<ProjectInfo>,
     local_override: Option<String>,
     jre: Option<JreInfo>,
     system_jre: SystemJreInfo,
     karate_jar: Option<JarInfo>,
     extensions: Vec<String>,
     config: ConfigInfo,
+    jvm_resources: JvmResources,
+    exit_codes: ExitCodesInfo,
 }
 
 #[derive(Serialize)]
@@ -27,6 +36,12 @@ struct PlatformInfo {
     key: String,
 }
 
+#[derive(Serialize)]
+struct ProjectInfo {
+    path: String,
+    source: String,
+}
+
 #[derive(Serialize)]
 struct JreInfo {
     version: String,
@@ -59,21 +74,63 @@ struct ConfigInfo {
     global_path: String,
     local_exists: bool,
     local_path: String,
+    profile: Option<String>,
+    errors: Vec<ConfigIssue>,
+    warnings: Vec<String>,
+    /// Environment for delegated commands, with values redacted
+    env: BTreeMap<String, EnvVar>,
+}
+
+#[derive(Serialize)]
+struct ExitCodesInfo {
+    mode: ExitCodeMode,
+    /// What a JVM exit N becomes
+    jvm_exit: String,
+    /// What a JVM killed by signal S becomes
+    signal: String,
+}
+
+impl ExitCodesInfo {
+    fn new(mode: ExitCodeMode) -> Self {
+        let (jvm_exit, signal) = match mode {
+            ExitCodeMode::Offset => (
+                "100 + N, capped at 255 (failed tests exit 101)",
+                "128 + S (SIGKILL exits 137)",
+            ),
+            ExitCodeMode::Raw => (
+                "N, clamped to 1-255 (failed tests exit 1)",
+                "128 + S (SIGKILL exits 137)",
+            ),
+            ExitCodeMode::Binary => ("1 for any non-zero N", "1"),
+        };
+        Self {
+            mode,
+            jvm_exit: jvm_exit.to_string(),
+            signal: signal.to_string(),
+        }
+    }
 }
 
-pub async fn run(args: DoctorArgs) -> Result<ExitCode> {
-    let report = build_report()?;
+pub async fn run(args: DoctorArgs, selection: &ConfigSelection) -> Result<ExitCode> {
+    let report = build_report(selection)?;
+
+    // Invalid config is an error here, even though delegated runs only warn about it
+    let exit_code = if report.config.errors.is_empty() {
+        ExitCode::Success
+    } else {
+        ExitCode::ConfigError
+    };
 
     if args.json {
         println!("{}", serde_json::to_string_pretty(&report)?);
-        return Ok(ExitCode::Success);
+        return Ok(exit_code);
     }
 
     print_report(&report);
-    Ok(ExitCode::Success)
+    Ok(exit_code)
 }
 
-fn build_report() -> Result<DoctorReport> {
+fn build_report(selection: &ConfigSelection) -> Result<DoctorReport> {
     let platform = Platform::detect()?;
     let paths = KaratePaths::new();
 
@@ -117,13 +174,92 @@ fn build_report() -> Result<DoctorReport> {
         .flat_map(|dir| list_jars(dir))
         .collect();
 
-    // Config info
+    // Config info. Invalid files are reported as errors rather than failing doctor;
+    // only a clean config is resolved (to show the applied profile).
     let local_config_path = KaratePaths::local_config();
+    let mut errors = validate_config_files()?;
+    // Resolving can still fail on a clean file: an unknown --profile, or an undefined
+    // variable in a placeholder
+    let resolved = if errors.is_empty() {
+        match load_merged_config(selection) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                errors.push(ConfigIssue {
+                    file: "resolved config".to_string(),
+                    message: format!("{e:#}"),
+                });
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let profile = resolved.as_ref().and_then(|c| c.profile.clone());
+
+    // Env values are often secrets: report names and sources only
+    let mut env = BTreeMap::new();
+    // The JVM's environment: the process's, with the config's values on top
+    let mut env_values: Vec<(String, String)> = std::env::vars().collect();
+    if let Some(config) = &resolved {
+        match resolve_env(config) {
+            Ok(vars) => {
+                env_values.extend(
+                    vars.iter()
+                        .map(|(name, var)| (name.clone(), var.value.clone())),
+                );
+                env = vars
+                    .into_iter()
+                    .map(|(name, var)| {
+                        let redacted = EnvVar {
+                            value: REDACTED.to_string(),
+                            source: var.source,
+                        };
+                        (name, redacted)
+                    })
+                    .collect();
+            }
+            Err(e) => errors.push(ConfigIssue {
+                file: "env_files".to_string(),
+                message: format!("{e:#}"),
+            }),
+        }
+    }
+
+    // Quoted jvm_opts strings used to be split on whitespace; flag them so a
+    // behavior change on upgrade is visible
+    let mut warnings = Vec::new();
+    if let Some(opts) = resolved.as_ref().and_then(|c| c.jvm_opts.as_ref()) {
+        if opts.has_quotes() {
+            warnings.push(format!(
+                "jvm_opts contains quotes and is parsed shell-style as {:?} \
+                 (older launchers split it on whitespace); use a JSON array to be explicit",
+                opts.args()?
+            ));
+        }
+    }
+
+    // Global jvm_opts only; per-command options can still override the defaults
+    let jvm_opts = match resolved.as_ref().and_then(|c| c.jvm_opts.as_ref()) {
+        Some(opts) => opts.args().unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let jvm_resources = JvmResources::resolve(&jvm_opts, &env_values);
+    let exit_codes = ExitCodesInfo::new(
+        resolved
+            .as_ref()
+            .and_then(|c| c.exit_code_mode)
+            .unwrap_or_default(),
+    );
+
     let config = ConfigInfo {
         global_exists: paths.global_config.exists(),
         global_path: paths.global_config.to_string_lossy().to_string(),
         local_exists: local_config_path.exists(),
         local_path: local_config_path.to_string_lossy().to_string(),
+        profile,
+        errors,
+        warnings,
+        env,
     };
 
     Ok(DoctorReport {
@@ -133,6 +269,10 @@ fn build_report() -> Result<DoctorReport> {
             key: platform.manifest_key(),
         },
         karate_home: paths.home.to_string_lossy().to_string(),
+        project_root: paths.project.as_ref().map(|p| ProjectInfo {
+            path: p.dir.to_string_lossy().to_string(),
+            source: p.source.to_string(),
+        }),
         local_override: paths
             .local
             .as_ref()
@@ -142,6 +282,8 @@ fn build_report() -> Result<DoctorReport> {
         karate_jar,
         extensions,
         config,
+        jvm_resources,
+        exit_codes,
     })
 }
 
@@ -203,9 +345,17 @@ fn print_report(report: &DoctorReport) {
 
     // Karate Home
     println!("{}", style("Karate Home").bold().underlined());
-    println!("  Global: {}", report.karate_home);
+    println!("  Global:  {}", report.karate_home);
+    match &report.project_root {
+        Some(project) => println!(
+            "  Project: {} {}",
+            project.path,
+            style(format!("({})", project.source)).dim()
+        ),
+        None => println!("  Project: {}", style("(none)").dim()),
+    }
     if let Some(local) = &report.local_override {
-        println!("  Local:  {} {}", local, style("(active)").cyan());
+        println!("  Local:   {} {}", local, style("(active)").cyan());
     }
     println!();
 
@@ -294,6 +444,41 @@ fn print_report(report: &DoctorReport) {
     }
     println!();
 
+    // Effective JVM resources
+    println!("{}", style("Effective JVM Resources").bold().underlined());
+    let limits = &report.jvm_resources.limits;
+    match limits.memory_bytes {
+        Some(bytes) => println!(
+            "  Memory limit: {:.1} GiB (cgroup)",
+            bytes as f64 / (1u64 << 30) as f64
+        ),
+        None => println!("  Memory limit: {}", style("none").dim()),
+    }
+    match limits.cpus {
+        Some(cpus) => println!("  CPU limit:    {cpus} (cgroup)"),
+        None => println!("  CPU limit:    {}", style("none").dim()),
+    }
+    if report.jvm_resources.defaults.is_empty() {
+        println!("  Defaults:     {}", style("none added").dim());
+    } else {
+        println!(
+            "  Defaults:     {}",
+            style(report.jvm_resources.defaults.join(" ")).cyan()
+        );
+    }
+    println!();
+
+    // How delegated JVM results become the launcher's exit code
+    println!("{}", style("Exit Codes").bold().underlined());
+    println!("  Mode:       {}", style(report.exit_codes.mode).cyan());
+    println!("  JVM exit N: {}", report.exit_codes.jvm_exit);
+    println!("  Signal S:   {}", report.exit_codes.signal);
+    println!(
+        "  {}",
+        style("Launcher errors: 1 general, 2 config, 3 network, 4 JRE, 5 timeout").dim()
+    );
+    println!();
+
     // Config
     println!("{}", style("Configuration").bold().underlined());
     if report.config.global_exists {
@@ -320,4 +505,21 @@ fn print_report(report: &DoctorReport) {
             style("(none) create with: karate config --local").dim()
         );
     }
+    if let Some(profile) = &report.config.profile {
+        println!("  Profile: {}", style(profile).cyan());
+    }
+    for (name, var) in &report.config.env {
+        println!(
+            "  Env:    {}={} {}",
+            name,
+            var.value,
+            style(format!("({})", var.source)).dim()
+        );
+    }
+    for issue in &report.config.errors {
+        println!("  {} {}", style("✗").red(), issue);
+    }
+    for warning in &report.config.warnings {
+        println!("  {} {}", style("!").yellow(), warning);
+    }
 }
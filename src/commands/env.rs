@@ -0,0 +1,104 @@
+//! Env command - emit shell exports for the launcher's java and Karate JAR.
+//!
+//! Usage: `eval "$(karate env)"`
+
+use crate::cli::{EnvArgs, Shell};
+use crate::config::load_merged_config;
+use crate::delegate::{plan_launch, LaunchOptions};
+use crate::error::ExitCode;
+use crate::jre::java_home;
+use crate::platform::KaratePaths;
+use anyhow::Result;
+
+pub async fn run(args: EnvArgs, options: &LaunchOptions) -> Result<ExitCode> {
+    let paths = KaratePaths::new();
+    let config = load_merged_config(&options.config)?;
+    let plan = plan_launch(&config, &paths, &[], &options.extra_classpath)?;
+
+    let shell = args.shell.unwrap_or_else(detect_shell);
+
+    let mut vars = vec![("KARATE_HOME", paths.home.to_string_lossy().to_string())];
+    let java_home = java_home(&plan.java);
+    if let Some(home) = &java_home {
+        vars.push(("JAVA_HOME", home.clone()));
+    }
+    vars.push(("KARATE_JAR", plan.jar.to_string_lossy().to_string()));
+    vars.push(("KARATE_CLASSPATH", plan.classpath_string()));
+
+    for (name, value) in &vars {
+        println!("{}", export(shell, name, value));
+    }
+
+    // Put the launcher's java first on PATH
+    if let Some(bin) = plan.java.parent() {
+        println!("{}", prepend_path(shell, &bin.to_string_lossy()));
+    }
+
+    Ok(ExitCode::Success)
+}
+
+/// Guess the shell from $SHELL (PowerShell on Windows).
+fn detect_shell() -> Shell {
+    if cfg!(windows) {
+        return Shell::Powershell;
+    }
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    match shell.rsplit('/').next() {
+        Some("zsh") => Shell::Zsh,
+        Some("fish") => Shell::Fish,
+        Some("pwsh") => Shell::Powershell,
+        _ => Shell::Bash,
+    }
+}
+
+/// A statement exporting one variable.
+fn export(shell: Shell, name: &str, value: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("export {name}={}", quote(shell, value)),
+        Shell::Fish => format!("set -gx {name} {}", quote(shell, value)),
+        Shell::Powershell => format!("$env:{name} = {}", quote(shell, value)),
+    }
+}
+
+/// A statement prepending a directory to PATH.
+fn prepend_path(shell: Shell, dir: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("export PATH={}:\"$PATH\"", quote(shell, dir)),
+        Shell::Fish => format!("set -gx PATH {} $PATH", quote(shell, dir)),
+        Shell::Powershell => format!(
+            "$env:PATH = {} + [IO.Path]::PathSeparator + $env:PATH",
+            quote(shell, dir)
+        ),
+    }
+}
+
+/// Single-quote a value for the given shell.
+fn quote(shell: Shell, value: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("'{}'", value.replace('\'', r"'\''")),
+        Shell::Fish => format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'")),
+        Shell::Powershell => format!("'{}'", value.replace('\'', "''")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_are_quoted_per_shell() {
+        let value = r"/opt/it's\jre";
+        assert_eq!(
+            export(Shell::Bash, "JAVA_HOME", value),
+            r"export JAVA_HOME='/opt/it'\''s\jre'"
+        );
+        assert_eq!(
+            export(Shell::Fish, "JAVA_HOME", value),
+            r"set -gx JAVA_HOME '/opt/it\'s\\jre'"
+        );
+        assert_eq!(
+            export(Shell::Powershell, "JAVA_HOME", value),
+            r"$env:JAVA_HOME = '/opt/it''s\jre'"
+        );
+    }
+}
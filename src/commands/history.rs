@@ -0,0 +1,91 @@
+//! History command - list recent delegated commands and replay them.
+
+use crate::cli::{HistoryArgs, HistoryRerunArgs, HistorySubcommand};
+use crate::delegate::{self, shell_join, LaunchOptions};
+use crate::error::ExitCode;
+use crate::history::{load_history, time_ago, HistoryEntry};
+use crate::platform::KaratePaths;
+use anyhow::{bail, Context, Result};
+use console::style;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub async fn run(args: HistoryArgs, options: LaunchOptions) -> Result<ExitCode> {
+    match args.subcommand {
+        Some(HistorySubcommand::Rerun(rerun_args)) => run_rerun(rerun_args, options).await,
+        None => run_list(args),
+    }
+}
+
+/// List the most recent entries, newest first.
+fn run_list(args: HistoryArgs) -> Result<ExitCode> {
+    let paths = KaratePaths::new();
+    let entries: Vec<HistoryEntry> = load_history(&paths)
+        .into_iter()
+        .rev()
+        .filter(|e| !args.failed || e.failed())
+        .take(args.limit)
+        .collect();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(ExitCode::Success);
+    }
+
+    if entries.is_empty() {
+        println!("{}", style("No runs recorded yet").dim());
+        return Ok(ExitCode::Success);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let cwd = std::env::current_dir().unwrap_or_default();
+    for entry in &entries {
+        let result = match (entry.exit_code, entry.signal) {
+            _ if entry.timed_out => style("timeout".to_string()).red(),
+            (Some(0), _) => style("passed".to_string()).green(),
+            (Some(code), _) => style(format!("exit {code}")).red(),
+            (None, Some(signal)) => style(format!("signal {signal}")).red(),
+            (None, None) => style("failed".to_string()).red(),
+        };
+        let elsewhere = if Path::new(&entry.cwd) != cwd {
+            style(format!(" (in {})", entry.cwd)).dim().to_string()
+        } else {
+            String::new()
+        };
+        println!(
+            "{:>5}  {:>8}  {:<8}  {:>7}  karate {}{}",
+            style(entry.id).bold(),
+            time_ago(entry.timestamp, now),
+            result,
+            format!("{:.1}s", entry.duration_ms as f64 / 1000.0),
+            shell_join(&entry.command_line()),
+            elsewhere
+        );
+    }
+
+    Ok(ExitCode::Success)
+}
+
+/// Run a recorded command again from the directory it ran in.
+async fn run_rerun(args: HistoryRerunArgs, mut options: LaunchOptions) -> Result<ExitCode> {
+    let paths = KaratePaths::new();
+    let Some(entry) = load_history(&paths).into_iter().find(|e| e.id == args.id) else {
+        bail!("No history entry {} (see 'karate history')", args.id);
+    };
+
+    entry.options.restore(&mut options)?;
+    let cwd = Path::new(&entry.cwd);
+    std::env::set_current_dir(cwd)
+        .with_context(|| format!("Cannot change to the run's directory {}", entry.cwd))?;
+    eprintln!(
+        "{} karate {} {}",
+        style("▶").cyan().bold(),
+        shell_join(&entry.command_line()),
+        style(format!("(in {})", entry.cwd)).dim()
+    );
+
+    delegate::run(entry.args, options).await
+}
@@ -1,19 +1,68 @@
 //! JRE command - JRE inspection and management.
 
 use crate::cli::{JreArgs, JreSubcommand};
-use crate::error::ExitCode;
+use crate::config::{load_merged_config, ExitCodeMode};
+use crate::delegate::{exit_code_in_mode, resolve_java, LaunchOptions, RunOutcome};
+use crate::error::{ExitCode, KarateError};
 use crate::jre::{find_active_jre, list_installed_jres};
 use crate::platform::Platform;
+use crate::process::{format_duration, run_supervised, Outcome};
 use anyhow::Result;
 use console::style;
+use tokio::process::Command;
 
-pub async fn run(args: JreArgs) -> Result<ExitCode> {
+pub async fn run(args: JreArgs, options: &LaunchOptions) -> Result<ExitCode> {
     match args.subcommand {
         JreSubcommand::List => run_list().await,
         JreSubcommand::Doctor => run_doctor().await,
+        JreSubcommand::Exec(exec_args) => exec(&exec_args.tool, &exec_args.args, options).await,
     }
 }
 
+/// Run a tool from the resolved JRE's bin/ directory, passing its exit code through.
+/// --timeout and --log-file apply as they do to delegated commands.
+pub async fn exec(tool: &str, args: &[String], options: &LaunchOptions) -> Result<ExitCode> {
+    let config = load_merged_config(&options.config)?;
+    let java = resolve_java(&config)?;
+    let bin = java
+        .parent()
+        .ok_or_else(|| KarateError::Jre(format!("Invalid java path: {}", java.display())))?;
+
+    let exe = [bin.join(tool), bin.join(format!("{tool}.exe"))]
+        .into_iter()
+        .find(|p| p.is_file())
+        .ok_or_else(|| {
+            KarateError::Jre(format!(
+                "'{}' not found in {} (a JRE may not ship JDK tools)",
+                tool,
+                bin.display()
+            ))
+        })?;
+
+    let mut cmd = Command::new(&exe);
+    cmd.args(args);
+    if let Some(home) = bin.parent() {
+        cmd.env("JAVA_HOME", home);
+    }
+
+    let outcome = match run_supervised(cmd, &options.supervision).await? {
+        Outcome::Exited(status) => RunOutcome::from_status(&status),
+        Outcome::TimedOut => {
+            let limit = options.supervision.timeout.unwrap_or_default();
+            eprintln!(
+                "{} Timed out after {}; {} stopped",
+                style("✗").red(),
+                format_duration(limit),
+                tool
+            );
+            RunOutcome::TimedOut
+        }
+    };
+
+    // A tool isn't a Karate run: pass its own code through whatever exit_code_mode says
+    Ok(exit_code_in_mode(outcome, ExitCodeMode::Raw))
+}
+
 /// List installed JREs.
 async fn run_list() -> Result<ExitCode> {
     let platform = Platform::detect()?;
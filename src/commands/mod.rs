@@ -1,9 +1,15 @@
 //! Command implementations for Rust-native commands.
 
+pub mod classpath;
 pub mod config;
 pub mod doctor;
+pub mod env;
+pub mod history;
 pub mod jre;
 pub mod plugin;
+pub mod rerun_failed;
 pub mod setup;
+pub mod shard;
 pub mod update;
 pub mod version;
+pub mod watch;
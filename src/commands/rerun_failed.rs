@@ -0,0 +1,201 @@
+//! Rerun-failed command - rerun the failed scenarios from the previous run's reports.
+
+use crate::cli::RerunFailedArgs;
+use crate::delegate::{exit_code, launch, LaunchOptions, RunOutcome};
+use crate::error::ExitCode;
+use crate::reports::{failed_scenarios, passed_scenarios_since, FailedScenario};
+use crate::shard::run_output;
+use anyhow::Result;
+use console::style;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A failed scenario and the attempt (if any) on which it passed.
+struct Rerun {
+    scenario: FailedScenario,
+    passed_on: Option<u32>,
+}
+
+pub async fn run(args: RerunFailedArgs, options: LaunchOptions) -> Result<ExitCode> {
+    let failed = failed_scenarios(&args.reports_dir)?;
+    if failed.is_empty() {
+        println!(
+            "{} No failed scenarios in {}",
+            style("✓").green(),
+            args.reports_dir.display()
+        );
+        return Ok(ExitCode::Success);
+    }
+
+    let mut reruns: Vec<Rerun> = failed
+        .into_iter()
+        .map(|scenario| Rerun {
+            scenario,
+            passed_on: None,
+        })
+        .collect();
+    let mut last_outcome = RunOutcome::Exited(0);
+    let fresh_reports = fresh_reports_dir(&args.reports_dir, &args.args);
+
+    for attempt in 1..=args.retries {
+        let pending: Vec<&FailedScenario> = reruns
+            .iter()
+            .filter(|r| r.passed_on.is_none())
+            .map(|r| &r.scenario)
+            .collect();
+        if pending.is_empty() {
+            break;
+        }
+
+        println!(
+            "{} Rerunning {} failed scenario(s) (attempt {} of {})",
+            style("▶").cyan().bold(),
+            pending.len(),
+            attempt,
+            args.retries
+        );
+        let mut run_args = vec!["run".to_string()];
+        run_args.extend(pending.iter().map(|s| s.selector()));
+        run_args.extend(args.args.iter().cloned());
+
+        let started = SystemTime::now();
+        last_outcome = launch(run_args, &options).await?;
+        if last_outcome == RunOutcome::DryRun {
+            return Ok(ExitCode::Success);
+        }
+        record_attempt(&mut reruns, attempt, last_outcome, &fresh_reports, started)?;
+    }
+
+    print_summary(&reruns, args.retries);
+
+    if reruns.iter().all(|r| r.passed_on.is_some()) {
+        return Ok(ExitCode::Success);
+    }
+    Ok(exit_code(last_outcome, &options.config))
+}
+
+/// Where the reruns write their reports: `karate-reports` under a `-o`/`--output`
+/// given in the run arguments, else the directory the failures were read from.
+fn fresh_reports_dir(reports_dir: &Path, run_args: &[String]) -> PathBuf {
+    match run_output(run_args) {
+        Some(output) => Path::new(&output).join("karate-reports"),
+        None => reports_dir.to_path_buf(),
+    }
+}
+
+/// Mark the pending scenarios that passed on an attempt. A clean exit means all of
+/// them passed; otherwise only a passing result in a report this attempt wrote counts,
+/// so a run that failed, timed out or was killed before writing reports passes nothing.
+fn record_attempt(
+    reruns: &mut [Rerun],
+    attempt: u32,
+    outcome: RunOutcome,
+    reports_dir: &Path,
+    started: SystemTime,
+) -> Result<()> {
+    let passed = match outcome {
+        RunOutcome::Exited(0) => None,
+        _ => Some(passed_scenarios_since(reports_dir, started)?),
+    };
+    for rerun in reruns.iter_mut().filter(|r| r.passed_on.is_none()) {
+        if passed
+            .as_ref()
+            .is_none_or(|passed| passed.contains(&rerun.scenario.selector()))
+        {
+            rerun.passed_on = Some(attempt);
+        }
+    }
+    Ok(())
+}
+
+/// Print the merged pass/fail result of all attempts.
+fn print_summary(reruns: &[Rerun], retries: u32) {
+    println!();
+    println!("{} Rerun Summary", style("▶").cyan().bold());
+    println!();
+
+    for rerun in reruns {
+        let selector = rerun.scenario.selector();
+        match rerun.passed_on {
+            Some(attempt) => println!(
+                "  {} {} {} {}",
+                style("✓").green(),
+                selector,
+                rerun.scenario.name,
+                style(format!("(passed on attempt {attempt})")).dim()
+            ),
+            None => println!(
+                "  {} {} {} {}",
+                style("✗").red(),
+                selector,
+                rerun.scenario.name,
+                style(format!("(failed {retries} attempt(s))")).dim()
+            ),
+        }
+    }
+
+    let passed = reruns.iter().filter(|r| r.passed_on.is_some()).count();
+    println!();
+    println!(
+        "  {} passed, {} failed",
+        style(passed).green(),
+        style(reruns.len() - passed).red()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending() -> Vec<Rerun> {
+        vec![Rerun {
+            scenario: FailedScenario {
+                feature: "features/users.feature".to_string(),
+                line: 12,
+                name: "create user".to_string(),
+            },
+            passed_on: None,
+        }]
+    }
+
+    #[test]
+    fn reads_fresh_reports_from_the_run_output() {
+        let reports = Path::new("target/karate-reports");
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            fresh_reports_dir(reports, &args(&["-t", "@smoke"])),
+            reports
+        );
+        for run_args in [&["-o", "out"][..], &["--output=out"], &["--output", "out"]] {
+            assert_eq!(
+                fresh_reports_dir(reports, &args(run_args)),
+                Path::new("out").join("karate-reports")
+            );
+        }
+    }
+
+    #[test]
+    fn failed_attempt_without_reports_passes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        for outcome in [
+            RunOutcome::Exited(1),
+            RunOutcome::Signaled(9),
+            RunOutcome::TimedOut,
+        ] {
+            let mut reruns = pending();
+            record_attempt(&mut reruns, 1, outcome, dir.path(), SystemTime::now()).unwrap();
+            assert_eq!(reruns[0].passed_on, None, "{outcome:?}");
+        }
+
+        let mut reruns = pending();
+        record_attempt(
+            &mut reruns,
+            2,
+            RunOutcome::Exited(0),
+            dir.path(),
+            SystemTime::now(),
+        )
+        .unwrap();
+        assert_eq!(reruns[0].passed_on, Some(2));
+    }
+}
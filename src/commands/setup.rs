@@ -1,9 +1,9 @@
 //! Setup command - first-run wizard and targeted setup.
 
 use crate::cli::SetupArgs;
-use crate::config::load_merged_config;
+use crate::config::{load_merged_config, validate_channel, ConfigSelection};
 use crate::download::{download_file, extract_tar_gz, resolve_justj_jre};
-use crate::error::ExitCode;
+use crate::error::{ExitCode, KarateError};
 use crate::jre::{find_active_jre, find_system_jre, JreSource, MIN_JAVA_VERSION};
 use crate::manifest::{fetch_manifest, MANIFEST_URL};
 use crate::platform::{KaratePaths, Platform};
@@ -18,7 +18,11 @@ const DEFAULT_JAVA_VERSION: u8 = MIN_JAVA_VERSION;
 /// Valid items for setup
 const VALID_ITEMS: &[&str] = &["jar", "jre"];
 
-pub async fn run(args: SetupArgs) -> Result<ExitCode> {
+pub async fn run(args: SetupArgs, selection: &ConfigSelection) -> Result<ExitCode> {
+    if let Some(channel) = &args.channel {
+        validate_channel(channel)?;
+    }
+
     // Determine which items to install
     let items: HashSet<String> = if args.all {
         // --all installs everything
@@ -36,7 +40,7 @@ pub async fn run(args: SetupArgs) -> Result<ExitCode> {
         set
     } else {
         // No flags = interactive wizard
-        return run_setup_wizard().await;
+        return run_setup_wizard(selection).await;
     };
 
     // Non-interactive install of specified items
@@ -46,6 +50,7 @@ pub async fn run(args: SetupArgs) -> Result<ExitCode> {
         args.java_version,
         args.karate_version,
         args.channel,
+        selection,
     )
     .await
 }
@@ -57,6 +62,7 @@ async fn run_setup_items(
     java_version: Option<String>,
     version_override: Option<String>,
     channel_override: Option<String>,
+    selection: &ConfigSelection,
 ) -> Result<ExitCode> {
     let platform = Platform::detect()?;
     let paths = KaratePaths::new();
@@ -141,7 +147,7 @@ async fn run_setup_items(
         // exact version; otherwise check for any JAR. Without the config-pin half, a pinned
         // setup would see "some jar exists" and skip downloading the pinned one.
         let pinned_version = version_override.clone().or_else(|| {
-            load_merged_config()
+            load_merged_config(selection)
                 .ok()
                 .map(|c| c.karate_version)
                 .filter(|v| v != "latest")
@@ -171,9 +177,11 @@ async fn run_setup_items(
                 &paths,
                 version_override.as_deref(),
                 channel_override.as_deref(),
+                selection,
             )
             .await?;
         }
+        prepare_cds_archive(selection).await;
         println!();
     }
 
@@ -187,7 +195,7 @@ async fn run_setup_items(
 }
 
 /// Full setup wizard (interactive).
-async fn run_setup_wizard() -> Result<ExitCode> {
+async fn run_setup_wizard(selection: &ConfigSelection) -> Result<ExitCode> {
     let platform = Platform::detect()?;
     let paths = KaratePaths::new();
 
@@ -244,8 +252,9 @@ async fn run_setup_wizard() -> Result<ExitCode> {
     if existing_jar.is_some() {
         println!("  {} Karate JAR already installed", style("✓").green());
     } else {
-        download_karate_jar(&paths, None, None).await?;
+        download_karate_jar(&paths, None, None, selection).await?;
     }
+    prepare_cds_archive(selection).await;
 
     println!();
     println!(
@@ -257,6 +266,17 @@ async fn run_setup_wizard() -> Result<ExitCode> {
     Ok(ExitCode::Success)
 }
 
+/// Create the class-data sharing archive with a warm-up run. A failure only means a
+/// slower JVM start, so it's reported but not fatal.
+pub async fn prepare_cds_archive(selection: &ConfigSelection) {
+    println!("  Preparing class-data sharing archive...");
+    match crate::cds::generate(selection).await {
+        Ok(Some(_)) => println!("  {} CDS archive ready", style("✓").green()),
+        Ok(None) => println!("  {} CDS archive skipped", style("-").dim()),
+        Err(e) => println!("  {} CDS archive skipped: {}", style("!").yellow(), e),
+    }
+}
+
 /// Download and extract JRE using JustJ manifest (same pattern as Red Hat vscode-java)
 async fn download_jre(platform: &Platform, paths: &KaratePaths, java_version: u8) -> Result<()> {
     let platform_key = platform.manifest_key();
@@ -297,21 +317,16 @@ async fn download_karate_jar(
     paths: &KaratePaths,
     version_override: Option<&str>,
     channel_override: Option<&str>,
+    selection: &ConfigSelection,
 ) -> Result<()> {
     // Load config to get channel and version preferences
-    let config = load_merged_config()?;
+    let config = load_merged_config(selection)?;
     let channel = channel_override.unwrap_or(&config.channel);
+    validate_channel(channel)?;
 
     println!("  Fetching release manifest from karate.sh...");
 
-    let manifest = fetch_manifest().await.map_err(|e| {
-        anyhow::anyhow!(
-            "Failed to fetch manifest from {}: {}\n\n\
-            Check your network connection or try again later.",
-            MANIFEST_URL,
-            e
-        )
-    })?;
+    let manifest = fetch_manifest().await?;
 
     // Determine version: CLI flag → config pin → latest from channel
     let version = if let Some(v) = version_override {
@@ -330,12 +345,10 @@ async fn download_karate_jar(
             .get_latest_version("karate", channel)
             .map(|s| s.to_string())
             .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "No '{}' karate version found in manifest.\n\
-                    Available channels: stable, beta\n\
-                    Set channel with: karate config --global",
-                    channel
-                )
+                KarateError::Config(format!(
+                    "No '{channel}' karate version found in manifest; \
+                    choose another channel with 'karate config --global'"
+                ))
             })?
     };
 
@@ -347,12 +360,10 @@ async fn download_karate_jar(
     let (url, sha256) = manifest
         .get_jar_download("karate", &version)
         .ok_or_else(|| {
-            anyhow::anyhow!(
-                "No download URL found for karate {} in manifest.\n\
-                Check available versions at: {}",
-                version,
-                MANIFEST_URL
-            )
+            KarateError::Config(format!(
+                "No download URL found for karate {version} in manifest; \
+                check available versions at {MANIFEST_URL}"
+            ))
         })?;
 
     let jar_name = format!("karate-{}.jar", version);
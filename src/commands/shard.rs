@@ -0,0 +1,85 @@
+//! Shard command - preview the CI split made by `--shard-index`/`--shard-total`.
+
+use crate::cli::{ShardArgs, ShardPlanArgs, ShardSubcommand};
+use crate::error::ExitCode;
+use crate::shard::{discover_features, select_features, ShardRoot, ShardSelection};
+use anyhow::{bail, Result};
+use console::style;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct ShardPlan {
+    total: usize,
+    features: usize,
+    shards: Vec<PlannedShard>,
+}
+
+#[derive(Serialize)]
+struct PlannedShard {
+    index: usize,
+    features: Vec<String>,
+}
+
+pub async fn run(args: ShardArgs) -> Result<ExitCode> {
+    match args.subcommand {
+        ShardSubcommand::Plan(plan_args) => run_plan(plan_args),
+    }
+}
+
+/// Show the features each shard would run.
+fn run_plan(args: ShardPlanArgs) -> Result<ExitCode> {
+    let total = args.shard_total as usize;
+    let index = args.shard_index.map(|i| i as usize);
+    if index.is_some_and(|i| i > total) {
+        bail!(
+            "--shard-index {} is out of range for --shard-total {}",
+            index.unwrap_or_default(),
+            total
+        );
+    }
+
+    let features = discover_features(&args.paths)?;
+    let root = ShardRoot::current()?;
+    let shards: Vec<PlannedShard> = (1..=total)
+        .filter(|i| index.is_none_or(|selected| selected == *i))
+        .map(|index| PlannedShard {
+            index,
+            features: select_features(&features, ShardSelection { index, total }, &root),
+        })
+        .collect();
+    let plan = ShardPlan {
+        total,
+        features: features.len(),
+        shards,
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+        return Ok(ExitCode::Success);
+    }
+
+    println!(
+        "{} {} feature(s) in {} shard(s)",
+        style("▶").cyan().bold(),
+        plan.features,
+        plan.total
+    );
+    for shard in &plan.shards {
+        println!();
+        println!(
+            "{} {}",
+            style(format!("Shard {}/{}", shard.index, plan.total))
+                .bold()
+                .underlined(),
+            style(format!("({} feature(s))", shard.features.len())).dim()
+        );
+        if shard.features.is_empty() {
+            println!("  {}", style("None").dim());
+        }
+        for feature in &shard.features {
+            println!("  {}", feature);
+        }
+    }
+
+    Ok(ExitCode::Success)
+}
@@ -1,10 +1,11 @@
 //! Update command - check for and install updates to Karate JAR and JRE.
 
 use crate::cli::UpdateArgs;
+use crate::commands::setup::prepare_cds_archive;
 use crate::commands::version::LAUNCHER_VERSION;
-use crate::config::load_merged_config;
+use crate::config::{load_merged_config, validate_channel, ConfigSelection};
 use crate::download::{download_file, extract_tar_gz, extract_zip, resolve_justj_jre};
-use crate::error::ExitCode;
+use crate::error::{ExitCode, KarateError};
 use crate::jre::MIN_JAVA_VERSION;
 use crate::manifest::{fetch_manifest, ReleasesManifest};
 use crate::platform::{KaratePaths, Os, Platform};
@@ -25,7 +26,7 @@ struct ComponentStatus {
     has_update: bool,
 }
 
-pub async fn run(args: UpdateArgs) -> Result<ExitCode> {
+pub async fn run(args: UpdateArgs, selection: &ConfigSelection) -> Result<ExitCode> {
     let platform = Platform::detect()?;
     let paths = KaratePaths::new();
 
@@ -67,8 +68,9 @@ pub async fn run(args: UpdateArgs) -> Result<ExitCode> {
     let mut cli_status: Option<ComponentStatus> = None;
 
     // Load config for channel preference (command line overrides config)
-    let config = load_merged_config()?;
+    let config = load_merged_config(selection)?;
     let channel = args.channel.as_deref().unwrap_or(&config.channel);
+    validate_channel(channel)?;
 
     // Fetch manifest once for JAR and CLI checks
     let manifest = if check_jar || check_cli {
@@ -84,7 +86,7 @@ pub async fn run(args: UpdateArgs) -> Result<ExitCode> {
             .as_ref()
             .and_then(|m| m.get_latest_version("karate", channel))
             .map(|s| s.to_string())
-            .ok_or_else(|| anyhow::anyhow!("No '{}' karate version found in manifest", channel))?;
+            .ok_or_else(|| no_channel_version("karate", channel))?;
 
         let has_update = match &installed {
             Some(v) => v != &latest,
@@ -135,9 +137,7 @@ pub async fn run(args: UpdateArgs) -> Result<ExitCode> {
             .as_ref()
             .and_then(|m| m.get_latest_version("karate-cli", channel))
             .map(|s| s.to_string())
-            .ok_or_else(|| {
-                anyhow::anyhow!("No '{}' karate-cli version found in manifest", channel)
-            })?;
+            .ok_or_else(|| no_channel_version("karate-cli", channel))?;
 
         let has_update = installed != latest;
 
@@ -281,6 +281,14 @@ pub async fn run(args: UpdateArgs) -> Result<ExitCode> {
         }
     }
 
+    // A new JAR or JRE invalidates the class-data sharing archive
+    let runtime_updated = [&jar_status, &jre_status]
+        .iter()
+        .any(|s| s.as_ref().map(|s| s.has_update).unwrap_or(false));
+    if runtime_updated {
+        prepare_cds_archive(selection).await;
+    }
+
     // Update CLI (last — if binary replacement disrupts process, JAR/JRE are already done)
     if let Some(ref status) = cli_status {
         if status.has_update {
@@ -360,11 +368,15 @@ async fn update_karate_jar(paths: &KaratePaths, channel: &str) -> Result<()> {
 
     let version = manifest
         .get_latest_version("karate", channel)
-        .ok_or_else(|| anyhow::anyhow!("No '{}' karate version found in manifest", channel))?;
+        .ok_or_else(|| no_channel_version("karate", channel))?;
 
     let (url, sha256) = manifest
         .get_jar_download("karate", version)
-        .ok_or_else(|| anyhow::anyhow!("No download URL found for karate {}", version))?;
+        .ok_or_else(|| {
+            KarateError::Network(format!(
+                "The release manifest has no download URL for karate {version}"
+            ))
+        })?;
 
     let jar_name = format!("karate-{}.jar", version);
     println!("  Downloading {}...", jar_name);
@@ -455,11 +467,11 @@ async fn update_cli_binary(
     let artifact = manifest
         .get_platform_download("karate-cli", version, platform)
         .ok_or_else(|| {
-            anyhow::anyhow!(
-                "No CLI binary found for platform '{}' in version {}",
+            KarateError::Network(format!(
+                "The release manifest has no CLI binary for platform '{}' in version {}",
                 platform.manifest_key(),
                 version
-            )
+            ))
         })?;
 
     let url = &artifact.url;
@@ -570,9 +582,18 @@ fn find_binary_in_dir(dir: &std::path::Path, binary_name: &str) -> Result<PathBu
         }
     }
 
-    anyhow::bail!(
-        "Could not find '{}' in extracted archive at {}",
+    Err(KarateError::DownloadFailed(format!(
+        "could not find '{}' in extracted archive at {}",
         binary_name,
         dir.display()
-    )
+    ))
+    .into())
+}
+
+/// A channel with no release of `artifact` in the manifest.
+fn no_channel_version(artifact: &str, channel: &str) -> KarateError {
+    KarateError::Config(format!(
+        "No '{channel}' {artifact} version found in manifest; \
+        choose another channel with --channel or 'karate config --global'"
+    ))
 }
@@ -0,0 +1,259 @@
+//! Watch command - rerun feature files as they change.
+//!
+//! Changes are batched until the files have been quiet for the debounce period. A
+//! changed `.feature` reruns just that feature; a changed `karate-config*.js` reruns
+//! everything; any other `.js` or `.json` reruns the features that mention its file
+//! name (everything when none do).
+
+use crate::cli::WatchArgs;
+use crate::delegate::{launch, LaunchOptions, RunOutcome};
+use crate::error::ExitCode;
+use crate::shard::{discover_features, IGNORED_DIRS};
+use anyhow::{Context, Result};
+use console::{style, Term};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Extensions of files that can change a run's result.
+const WATCHED_EXTENSIONS: &[&str] = &["feature", "js", "json"];
+
+pub async fn run(args: WatchArgs, options: LaunchOptions) -> Result<ExitCode> {
+    let roots = args
+        .paths
+        .iter()
+        .map(|p| std::fs::canonicalize(p).with_context(|| format!("Watch path not found: {}", p)))
+        .collect::<Result<Vec<PathBuf>>>()?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .context("Failed to start the file watcher")?;
+    for root in &roots {
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", root.display()))?;
+    }
+
+    let mut run_number = 0;
+    let mut features = discover_features(&args.paths)?;
+    loop {
+        if features.is_empty() {
+            println!("{} No feature files to run", style("!").yellow());
+        } else {
+            run_number += 1;
+            if !args.no_clear {
+                let _ = Term::stdout().clear_screen();
+            }
+            run_features(run_number, &features, &args.args, &options).await?;
+        }
+        println!(
+            "{}",
+            style(format!(
+                "Watching {} for changes (Ctrl+C to stop)",
+                args.paths.join(", ")
+            ))
+            .dim()
+        );
+
+        let changed = next_changes(&mut rx, &roots, args.debounce).await?;
+        let all = discover_features(&args.paths)?;
+        features = features_to_run(&changed, &all);
+    }
+}
+
+/// Run features through the delegation path and print a one-line result.
+async fn run_features(
+    run_number: u32,
+    features: &[String],
+    extra_args: &[String],
+    options: &LaunchOptions,
+) -> Result<()> {
+    let mut run_args = vec!["run".to_string()];
+    run_args.extend(features.iter().cloned());
+    run_args.extend(extra_args.iter().cloned());
+
+    let started = Instant::now();
+    let outcome = launch(run_args, options).await?;
+    let details = style(format!(
+        "· {} feature(s) · {:.1}s",
+        features.len(),
+        started.elapsed().as_secs_f64()
+    ))
+    .dim();
+
+    println!();
+    match outcome {
+        RunOutcome::Exited(0) | RunOutcome::DryRun => println!(
+            "{} {} {}",
+            style("✓").green().bold(),
+            style(format!("Run {run_number} passed")).green(),
+            details
+        ),
+        RunOutcome::Exited(code) => println!(
+            "{} {} {}",
+            style("✗").red().bold(),
+            style(format!("Run {run_number} failed (exit {code})")).red(),
+            details
+        ),
+        RunOutcome::Signaled(signal) => println!(
+            "{} {} {}",
+            style("✗").red().bold(),
+            style(format!("Run {run_number} killed by signal {signal}")).red(),
+            details
+        ),
+        RunOutcome::TimedOut => println!(
+            "{} {} {}",
+            style("✗").red().bold(),
+            style(format!("Run {run_number} timed out")).red(),
+            details
+        ),
+    }
+    Ok(())
+}
+
+/// Wait for a relevant change, then collect changes until the files have been quiet
+/// for `debounce`.
+async fn next_changes(
+    rx: &mut UnboundedReceiver<notify::Result<Event>>,
+    roots: &[PathBuf],
+    debounce: Duration,
+) -> Result<Vec<PathBuf>> {
+    let mut changed = BTreeSet::new();
+    while changed.is_empty() {
+        let event = rx.recv().await.context("File watcher stopped")?;
+        collect_changes(event?, roots, &mut changed);
+    }
+    while let Ok(Some(event)) = tokio::time::timeout(debounce, rx.recv()).await {
+        collect_changes(event?, roots, &mut changed);
+    }
+    Ok(changed.into_iter().collect())
+}
+
+fn collect_changes(event: Event, roots: &[PathBuf], changed: &mut BTreeSet<PathBuf>) {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    changed.extend(event.paths.into_iter().filter(|p| is_watched(p, roots)));
+}
+
+/// Whether a changed path is a watched file type outside hidden and build output
+/// directories (runs write JSON reports under `target/`).
+fn is_watched(path: &Path, roots: &[PathBuf]) -> bool {
+    let watched_type = path
+        .extension()
+        .is_some_and(|e| WATCHED_EXTENSIONS.iter().any(|w| e == *w));
+    let Some(relative) = roots.iter().find_map(|r| path.strip_prefix(r).ok()) else {
+        return false;
+    };
+    let parents: Vec<String> = relative
+        .parent()
+        .into_iter()
+        .flat_map(|p| p.components())
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    watched_type
+        && !parents
+            .iter()
+            .any(|c| c.starts_with('.') || IGNORED_DIRS.contains(&c.as_str()))
+}
+
+/// The features to rerun for a batch of changed files, out of all discovered ones.
+fn features_to_run(changed: &[PathBuf], all: &[String]) -> Vec<String> {
+    let file_name = |path: &Path| {
+        path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    let is_config = |path: &Path| {
+        let name = file_name(path);
+        name.starts_with("karate-config") && name.ends_with(".js")
+    };
+    if changed.iter().any(|p| is_config(p)) {
+        return all.to_vec();
+    }
+
+    let mut selected = BTreeSet::new();
+    for path in changed {
+        if path.extension().is_some_and(|e| e == "feature") {
+            // Deleted features have nothing left to run
+            if let Some(feature) = all.iter().find(|f| same_file(f, path)) {
+                selected.insert(feature.clone());
+            }
+            continue;
+        }
+
+        let name = file_name(path);
+        let dependents: Vec<&String> = all
+            .iter()
+            .filter(|f| {
+                std::fs::read_to_string(f)
+                    .map(|content| content.contains(&name))
+                    .unwrap_or(false)
+            })
+            .collect();
+        if dependents.is_empty() {
+            return all.to_vec();
+        }
+        selected.extend(dependents.into_iter().cloned());
+    }
+    all.iter()
+        .filter(|f| selected.contains(*f))
+        .cloned()
+        .collect()
+}
+
+/// Whether a discovered feature path refers to a changed (absolute) path.
+fn same_file(feature: &str, path: &Path) -> bool {
+    std::fs::canonicalize(feature).is_ok_and(|f| f == path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_reports_and_other_files() {
+        let root = PathBuf::from("/project");
+        let roots = [root.clone()];
+        assert!(is_watched(&root.join("src/users.feature"), &roots));
+        assert!(is_watched(&root.join("src/karate-config.js"), &roots));
+        assert!(!is_watched(
+            &root.join("target/karate-reports/a.json"),
+            &roots
+        ));
+        assert!(!is_watched(&root.join(".git/index.json"), &roots));
+        assert!(!is_watched(&root.join("src/Users.java"), &roots));
+        assert!(!is_watched(Path::new("/elsewhere/a.feature"), &roots));
+    }
+
+    #[test]
+    fn selects_features_for_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        let users = root.join("users.feature");
+        let orders = root.join("orders.feature");
+        std::fs::write(&users, "* def user = read('user.json')").unwrap();
+        std::fs::write(&orders, "* def order = { id: 1 }").unwrap();
+        let all: Vec<String> = [&orders, &users]
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+
+        let users_only = vec![all[1].clone()];
+        assert_eq!(
+            features_to_run(std::slice::from_ref(&users), &all),
+            users_only
+        );
+        assert_eq!(features_to_run(&[root.join("user.json")], &all), users_only);
+        assert_eq!(features_to_run(&[root.join("helpers.js")], &all), all);
+        assert_eq!(
+            features_to_run(&[users, root.join("karate-config-dev.js")], &all),
+            all
+        );
+        assert!(features_to_run(&[root.join("deleted.feature")], &all).is_empty());
+    }
+}
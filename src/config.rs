@@ -1,9 +1,30 @@
 //! Configuration management for Karate CLI.
 
+use crate::env::REDACTED;
+use crate::error::KarateError;
 use crate::platform::KaratePaths;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Valid release channels.
+pub const VALID_CHANNELS: &[&str] = &["stable", "beta", "nightly"];
+
+/// Check a release channel given on the command line or in config.
+pub fn validate_channel(channel: &str) -> Result<(), KarateError> {
+    if VALID_CHANNELS.contains(&channel) {
+        return Ok(());
+    }
+    Err(KarateError::Config(format!(
+        "Unknown channel '{channel}' (expected {})",
+        VALID_CHANNELS.join(", ")
+    )))
+}
+
+/// Valid `exit_code_mode` values.
+pub const VALID_EXIT_CODE_MODES: &[&str] = &["offset", "raw", "binary"];
 
 /// Karate CLI configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,13 +48,263 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dist_path: Option<String>,
 
-    /// Additional JVM options
+    /// Additional JVM options: a shell-style string or an array of arguments
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub jvm_opts: Option<String>,
+    pub jvm_opts: Option<JvmOpts>,
 
     /// Check for updates on run
     #[serde(default = "default_check_updates")]
     pub check_updates: bool,
+
+    /// Use AppCDS archives from cache/cds/ (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cds: Option<bool>,
+
+    /// How a delegated JVM's exit code becomes the launcher's (default: offset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code_mode: Option<ExitCodeMode>,
+
+    /// Classpath entries added by the selected profile, after ext JARs and before --cp
+    /// flags. Not a config file key; set only by `apply_profile`.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub classpath: Vec<String>,
+
+    /// Environment variables set for delegated commands
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+
+    /// Dotenv files loaded for delegated commands, in order (later files win)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_files: Vec<String>,
+
+    /// Profile applied when neither --profile nor KARATE_PROFILE is given.
+    /// In the resolved config, this is the profile that was actually applied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// Named partial configs, selected per invocation with --profile
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// Per-command settings, keyed by the first delegated argument (run, mock, mcp, ...)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub commands: BTreeMap<String, CommandConfig>,
+
+    /// Java agents attached to delegated commands, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub java_agents: Vec<JavaAgent>,
+}
+
+/// A Java agent passed as `-javaagent:`, from a local JAR or a manifest artifact.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JavaAgent {
+    /// Local agent JAR
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Manifest artifact ID, downloaded into dist/agents/
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// Artifact version (required with `id`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Agent options, appended as `-javaagent:<jar>=<options>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<String>,
+
+    /// Expected SHA-256 of a local agent JAR
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// Settings for one delegated command, applied on top of the global options.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandConfig {
+    /// JVM options added after the global `jvm_opts`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jvm_opts: Option<JvmOpts>,
+
+    /// System properties, passed as `-Dkey=value`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub system_properties: BTreeMap<String, String>,
+
+    /// Default arguments inserted after the command name, before user arguments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+impl CommandConfig {
+    /// JVM arguments for this command: its `jvm_opts`, then system properties.
+    pub fn jvm_args(&self) -> Result<Vec<String>> {
+        let mut args = match &self.jvm_opts {
+            Some(opts) => opts.args()?,
+            None => Vec::new(),
+        };
+        args.extend(
+            self.system_properties
+                .iter()
+                .map(|(key, value)| format!("-D{key}={value}")),
+        );
+        Ok(args)
+    }
+}
+
+/// A named partial config, merged on top of the resolved config when selected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub karate_version: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jvm_opts: Option<JvmOpts>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub classpath: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+/// How a non-zero JVM exit code is passed through by the launcher.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExitCodeMode {
+    /// 100 + code, keeping launcher errors (1-5) distinguishable
+    #[default]
+    Offset,
+    /// The JVM's code unchanged
+    Raw,
+    /// 1 for any failure
+    Binary,
+}
+
+impl std::fmt::Display for ExitCodeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitCodeMode::Offset => write!(f, "offset"),
+            ExitCodeMode::Raw => write!(f, "raw"),
+            ExitCodeMode::Binary => write!(f, "binary"),
+        }
+    }
+}
+
+/// JVM options, either as one string or as a list of arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum JvmOpts {
+    /// A single string, split with POSIX-style quoting: `-Dkarate.options="--tags @smoke"`
+    Line(String),
+    /// One JVM argument per element, used verbatim
+    List(Vec<String>),
+}
+
+impl JvmOpts {
+    /// The individual JVM arguments.
+    pub fn args(&self) -> Result<Vec<String>> {
+        match self {
+            JvmOpts::Line(line) => split_shell_words(line)
+                .map_err(|e| KarateError::Config(format!("Invalid jvm_opts: {e}")).into()),
+            JvmOpts::List(args) => Ok(args.clone()),
+        }
+    }
+
+    /// Whether this is a string containing quotes, which older launchers split on
+    /// whitespace (so the quotes ended up inside the JVM arguments).
+    pub fn has_quotes(&self) -> bool {
+        matches!(self, JvmOpts::Line(line) if line.contains(['"', '\'']))
+    }
+
+    fn map(&self, f: impl Fn(&str) -> Result<String, String>) -> Result<JvmOpts, String> {
+        Ok(match self {
+            JvmOpts::Line(line) => JvmOpts::Line(f(line)?),
+            JvmOpts::List(args) => {
+                JvmOpts::List(args.iter().map(|a| f(a)).collect::<Result<Vec<_>, _>>()?)
+            }
+        })
+    }
+}
+
+/// Split a string into words using POSIX shell-style quoting.
+///
+/// Single quotes are literal; inside double quotes a backslash escapes `"`, `\`,
+/// `$` and `` ` ``. Outside quotes a backslash only escapes whitespace, quotes and
+/// backslashes, so Windows paths like `C:\tools` pass through unchanged.
+fn split_shell_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(format!("unterminated single quote in '{line}'")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.peek() {
+                            Some(&next @ ('"' | '\\' | '$' | '`')) => {
+                                word.push(next);
+                                chars.next();
+                            }
+                            _ => word.push('\\'),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(format!("unterminated double quote in '{line}'")),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                match chars.peek() {
+                    Some(&next) if next.is_whitespace() || matches!(next, '"' | '\'' | '\\') => {
+                        word.push(next);
+                        chars.next();
+                    }
+                    _ => word.push('\\'),
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Per-invocation config choices made on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSelection {
+    /// Profile to apply (--profile or KARATE_PROFILE); overrides the config's `profile`
+    pub profile: Option<String>,
+    /// Env files loaded after any from config (--env-file), relative to cwd
+    pub env_files: Vec<String>,
 }
 
 fn default_channel() -> String {
@@ -57,22 +328,47 @@ impl Default for Config {
             dist_path: None,
             jvm_opts: None,
             check_updates: default_check_updates(),
+            cds: None,
+            exit_code_mode: None,
+            classpath: Vec::new(),
+            env: BTreeMap::new(),
+            env_files: Vec::new(),
+            profile: None,
+            profiles: BTreeMap::new(),
+            commands: BTreeMap::new(),
+            java_agents: Vec::new(),
         }
     }
 }
 
 impl Config {
+    /// A copy that is safe to print: `env` values, including those in profiles, are
+    /// replaced with `REDACTED`.
+    pub fn redacted(&self) -> Config {
+        let mut config = self.clone();
+        let envs = std::iter::once(&mut config.env)
+            .chain(config.profiles.values_mut().map(|profile| &mut profile.env));
+        for env in envs {
+            for value in env.values_mut() {
+                *value = REDACTED.to_string();
+            }
+        }
+        config
+    }
+
     /// Load config from a file.
     pub fn load_from_file(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Ok(Config::default());
         }
 
-        let content = std::fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config from {}", path.display()))?;
+        let content = std::fs::read_to_string(path).with_context(|| {
+            KarateError::Config(format!("Failed to read config from {}", path.display()))
+        })?;
 
-        serde_json::from_str(&content)
-            .with_context(|| format!("Failed to parse config from {}", path.display()))
+        serde_json::from_str(&content).with_context(|| {
+            KarateError::Config(format!("Failed to parse config from {}", path.display()))
+        })
     }
 
     /// Save config to a file.
@@ -110,35 +406,786 @@ impl Config {
         if !other.check_updates {
             self.check_updates = false;
         }
+        if other.cds.is_some() {
+            self.cds = other.cds;
+        }
+        if other.exit_code_mode.is_some() {
+            self.exit_code_mode = other.exit_code_mode;
+        }
+        // Env is composable across config files, like ext/ JARs
+        self.env
+            .extend(other.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.env_files.extend(other.env_files.iter().cloned());
+        if other.profile.is_some() {
+            self.profile = other.profile.clone();
+        }
+        self.profiles
+            .extend(other.profiles.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.commands
+            .extend(other.commands.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.java_agents.extend(other.java_agents.iter().cloned());
+    }
+
+    /// Apply a profile on top of this config (profile takes precedence).
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        if let Some(channel) = &profile.channel {
+            self.channel = channel.clone();
+        }
+        if let Some(version) = &profile.karate_version {
+            self.karate_version = version.clone();
+        }
+        if profile.jvm_opts.is_some() {
+            self.jvm_opts = profile.jvm_opts.clone();
+        }
+        self.classpath.extend(profile.classpath.iter().cloned());
+        self.env
+            .extend(profile.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    /// Resolve placeholders, `~` and relative paths in values read from one config file.
+    /// Must run before merging, so each value is resolved against the file that defined it.
+    pub fn resolve_values(&mut self, resolver: &Resolver) -> Result<()> {
+        self.try_resolve_values(resolver)
+            .map_err(|e| KarateError::Config(e).into())
+    }
+
+    fn try_resolve_values(&mut self, resolver: &Resolver) -> Result<(), String> {
+        if let Some(path) = &self.jre_path {
+            self.jre_path = Some(resolver.path(path)?);
+        }
+        if let Some(path) = &self.dist_path {
+            self.dist_path = Some(resolver.path(path)?);
+        }
+        if let Some(opts) = &self.jvm_opts {
+            self.jvm_opts = Some(opts.map(|o| resolver.text(o))?);
+        }
+        for value in self.env.values_mut() {
+            *value = resolver.text(value)?;
+        }
+        for file in &mut self.env_files {
+            *file = resolver.path(file)?;
+        }
+        for profile in self.profiles.values_mut() {
+            if let Some(opts) = &profile.jvm_opts {
+                profile.jvm_opts = Some(opts.map(|o| resolver.text(o))?);
+            }
+            for entry in &mut profile.classpath {
+                *entry = resolver.path(entry)?;
+            }
+            for value in profile.env.values_mut() {
+                *value = resolver.text(value)?;
+            }
+        }
+        for command in self.commands.values_mut() {
+            if let Some(opts) = &command.jvm_opts {
+                command.jvm_opts = Some(opts.map(|o| resolver.text(o))?);
+            }
+            for value in command.system_properties.values_mut() {
+                *value = resolver.text(value)?;
+            }
+            for arg in &mut command.args {
+                *arg = resolver.text(arg)?;
+            }
+        }
+        for agent in &mut self.java_agents {
+            if let Some(path) = &agent.path {
+                agent.path = Some(resolver.path(path)?);
+            }
+            if let Some(options) = &agent.options {
+                agent.options = Some(resolver.text(options)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Select and apply a profile by name. `None` falls back to the config's own
+    /// `profile` default; with neither set, no profile is applied.
+    pub fn resolve_profile(&mut self, selected: Option<&str>) -> Result<()> {
+        let Some(name) = selected
+            .map(str::to_string)
+            .or_else(|| self.profile.clone())
+        else {
+            return Ok(());
+        };
+
+        let profile = self.profiles.get(&name).cloned().ok_or_else(|| {
+            let available: Vec<&str> = self.profiles.keys().map(|k| k.as_str()).collect();
+            KarateError::Config(format!(
+                "Unknown profile '{name}' (available: {})",
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            ))
+        })?;
+
+        self.apply_profile(&profile);
+        self.profile = Some(name);
+        Ok(())
+    }
+
+    /// Apply command-line choices: the selected profile, then extra env files resolved
+    /// with `resolver` (--env-file paths are relative to cwd, like any other
+    /// command-line path).
+    pub fn apply_selection(
+        &mut self,
+        selection: &ConfigSelection,
+        resolver: &Resolver,
+    ) -> Result<()> {
+        self.resolve_profile(selection.profile.as_deref())?;
+        for file in &selection.env_files {
+            self.env_files
+                .push(resolver.path(file).map_err(KarateError::Config)?);
+        }
+        Ok(())
     }
 }
 
 /// Load and merge all applicable configs.
-/// Precedence: project config > global config > defaults
-pub fn load_merged_config() -> Result<Config> {
+/// Precedence: selected profile > project config > global config > defaults
+pub fn load_merged_config(selection: &ConfigSelection) -> Result<Config> {
     let paths = KaratePaths::new();
 
     // Start with defaults
     let mut config = Config::default();
 
-    // Load and merge global config
-    let global_config = Config::load_from_file(&paths.global_config)?;
+    let project_dir = KaratePaths::project_dir();
+
+    // Load and merge global config (relative paths resolve against the karate home)
+    let mut global_config = Config::load_from_file(&paths.global_config)?;
+    global_config
+        .resolve_values(&Resolver::new(&paths.home, &project_dir))
+        .with_context(|| format!("Invalid config in {}", paths.global_config.display()))?;
     config.merge(&global_config);
 
-    // Load and merge project config if it exists
+    // Load and merge project config if it exists (relative paths resolve against the project)
     let local_config_path = KaratePaths::local_config();
     if local_config_path.exists() {
-        let local_config = Config::load_from_file(&local_config_path)?;
+        let mut local_config = Config::load_from_file(&local_config_path)?;
+        local_config
+            .resolve_values(&Resolver::new(&project_dir, &project_dir))
+            .with_context(|| format!("Invalid config in {}", local_config_path.display()))?;
         config.merge(&local_config);
     }
 
+    let cwd = std::env::current_dir().context("Could not determine current directory")?;
+    config.apply_selection(selection, &Resolver::new(&cwd, &project_dir))?;
+
     Ok(config)
 }
 
+/// Resolves config values read from one file.
+///
+/// - `${env:VAR}` or `${VAR}` expands to an environment variable (error if undefined)
+/// - `${project_dir}` expands to the project root (or cwd outside a project)
+/// - `$${` is a literal `${`
+/// - for path values, a leading `~` expands to the home directory and relative
+///   paths resolve against the base directory of the defining file
+pub struct Resolver {
+    base_dir: PathBuf,
+    project_dir: PathBuf,
+}
+
+impl Resolver {
+    pub fn new(base_dir: &Path, project_dir: &Path) -> Self {
+        Resolver {
+            base_dir: base_dir.to_path_buf(),
+            project_dir: project_dir.to_path_buf(),
+        }
+    }
+
+    /// Expand placeholders in a plain string value.
+    pub fn text(&self, value: &str) -> Result<String, String> {
+        let mut out = String::new();
+        let mut rest = value;
+
+        while let Some(start) = rest.find("${") {
+            if rest[..start].ends_with('$') {
+                out.push_str(&rest[..start - 1]);
+                out.push_str("${");
+                rest = &rest[start + 2..];
+                continue;
+            }
+            out.push_str(&rest[..start]);
+
+            let end = rest[start..]
+                .find('}')
+                .map(|i| i + start)
+                .ok_or_else(|| format!("Unterminated placeholder in '{value}'"))?;
+            let name = &rest[start + 2..end];
+
+            if name == "project_dir" {
+                out.push_str(&self.project_dir.to_string_lossy());
+            } else {
+                // `${env:VAR}` and a bare `${VAR}` both read the environment
+                let var = name.strip_prefix("env:").unwrap_or(name);
+                if var.is_empty() {
+                    return Err(format!(
+                        "Empty placeholder in '{value}' (use $${{ for a literal)"
+                    ));
+                }
+                let resolved = std::env::var(var).map_err(|_| {
+                    format!("Environment variable '{var}' is not defined (referenced in '{value}')")
+                })?;
+                out.push_str(&resolved);
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// Expand placeholders and `~`, and resolve a relative path against the base directory.
+    pub fn path(&self, value: &str) -> Result<String, String> {
+        let expanded = self.text(value)?;
+
+        let path = match expanded.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+                let home = dirs::home_dir()
+                    .ok_or_else(|| format!("Cannot expand '~' in '{value}': no home directory"))?;
+                home.join(rest.trim_start_matches(['/', '\\']))
+            }
+            _ => PathBuf::from(&expanded),
+        };
+
+        let path = if path.is_relative() {
+            // Drop `./` segments so "./tools/karate" reads cleanly once joined
+            let relative: PathBuf = path
+                .components()
+                .filter(|c| *c != Component::CurDir)
+                .collect();
+            self.base_dir.join(relative)
+        } else {
+            path
+        };
+
+        Ok(path.to_string_lossy().to_string())
+    }
+}
+
+/// A problem found while validating a config file.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigIssue {
+    pub file: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.file, self.message)
+    }
+}
+
+/// JSON Schema for karate-cli.json.
+/// This is also the source of truth for which keys validation accepts.
+pub fn config_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Karate CLI configuration (karate-cli.json)",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "$schema": {
+                "type": "string",
+                "description": "JSON Schema reference for editor support"
+            },
+            "channel": {
+                "type": "string",
+                "enum": VALID_CHANNELS,
+                "default": "stable",
+                "description": "Release channel"
+            },
+            "karate_version": {
+                "type": "string",
+                "default": "latest",
+                "description": "Karate version to use, or \"latest\""
+            },
+            "jre_path": {
+                "type": ["string", "null"],
+                "description": "Explicit path to a JRE directory (default: ~/.karate/jre/)"
+            },
+            "dist_path": {
+                "type": ["string", "null"],
+                "description": "Explicit path to a directory containing the Karate JAR (default: ~/.karate/dist/)"
+            },
+            "jvm_opts": {
+                "oneOf": [
+                    { "type": "string" },
+                    { "type": "array", "items": { "type": "string" } },
+                    { "type": "null" }
+                ],
+                "description": "Additional JVM options: a shell-style string or an array of arguments"
+            },
+            "check_updates": {
+                "type": "boolean",
+                "default": true,
+                "description": "Check for updates on run"
+            },
+            "cds": {
+                "type": "boolean",
+                "default": true,
+                "description": "Use AppCDS archives generated at install time for faster JVM startup"
+            },
+            "exit_code_mode": {
+                "type": "string",
+                "enum": VALID_EXIT_CODE_MODES,
+                "default": "offset",
+                "description": "Launcher exit code for a failed JVM: offset (100 + code), raw (the JVM's code) or binary (1)"
+            },
+            "env": {
+                "type": "object",
+                "additionalProperties": { "type": "string" },
+                "description": "Environment variables for delegated commands"
+            },
+            "env_files": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Dotenv files for delegated commands, relative to the defining config file"
+            },
+            "profile": {
+                "type": "string",
+                "description": "Profile applied when --profile / KARATE_PROFILE is not given"
+            },
+            "profiles": {
+                "type": "object",
+                "additionalProperties": profile_schema(),
+                "description": "Named partial configs, selected with --profile or KARATE_PROFILE"
+            },
+            "commands": {
+                "type": "object",
+                "additionalProperties": command_schema(),
+                "description": "Per-command settings keyed by the delegated command (run, mock, mcp, ...)"
+            },
+            "java_agents": {
+                "type": "array",
+                "items": java_agent_schema(),
+                "description": "Java agents attached to delegated commands with -javaagent:, in order"
+            }
+        }
+    })
+}
+
+/// JSON Schema for a single entry in `java_agents`.
+fn java_agent_schema() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "path": {
+                "type": "string",
+                "description": "Local agent JAR, relative to the defining config file"
+            },
+            "id": {
+                "type": "string",
+                "description": "Manifest artifact ID, downloaded into dist/agents/"
+            },
+            "version": {
+                "type": "string",
+                "description": "Artifact version (required with id)"
+            },
+            "options": {
+                "type": "string",
+                "description": "Agent options, passed as -javaagent:<jar>=<options>"
+            },
+            "sha256": {
+                "type": "string",
+                "description": "Expected SHA-256 of a local agent JAR"
+            }
+        },
+        "oneOf": [
+            { "required": ["path"], "not": { "required": ["id"] } },
+            { "required": ["id", "version"], "not": { "required": ["path"] } }
+        ]
+    })
+}
+
+/// JSON Schema for a single entry in `commands`.
+fn command_schema() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "jvm_opts": {
+                "oneOf": [
+                    { "type": "string" },
+                    { "type": "array", "items": { "type": "string" } }
+                ],
+                "description": "JVM options added after the global jvm_opts"
+            },
+            "system_properties": {
+                "type": "object",
+                "additionalProperties": { "type": "string" },
+                "description": "System properties passed as -Dkey=value"
+            },
+            "args": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Default arguments inserted before user arguments"
+            }
+        }
+    })
+}
+
+/// JSON Schema for a single entry in `profiles`.
+fn profile_schema() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "channel": { "type": "string", "enum": VALID_CHANNELS },
+            "karate_version": { "type": "string" },
+            "jvm_opts": {
+                "oneOf": [
+                    { "type": "string" },
+                    { "type": "array", "items": { "type": "string" } }
+                ]
+            },
+            "classpath": { "type": "array", "items": { "type": "string" } },
+            "env": { "type": "object", "additionalProperties": { "type": "string" } }
+        }
+    })
+}
+
+/// Validate all applicable config files (global, then project).
+pub fn validate_config_files() -> Result<Vec<ConfigIssue>> {
+    let paths = KaratePaths::new();
+    let project_dir = KaratePaths::project_dir();
+    let mut issues = validate_file(
+        &paths.global_config,
+        &Resolver::new(&paths.home, &project_dir),
+    )?;
+    issues.extend(validate_file(
+        &KaratePaths::local_config(),
+        &Resolver::new(&project_dir, &project_dir),
+    )?);
+    Ok(issues)
+}
+
+/// Validate a single config file. A missing file has no issues.
+pub fn validate_file(path: &Path, resolver: &Resolver) -> Result<Vec<ConfigIssue>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config from {}", path.display()))?;
+    let messages = match serde_json::from_str::<Value>(&content) {
+        Ok(value) => validate_value(&value, resolver),
+        Err(e) => vec![format!("invalid JSON: {e}")],
+    };
+
+    Ok(messages
+        .into_iter()
+        .map(|message| ConfigIssue {
+            file: path.to_string_lossy().to_string(),
+            message,
+        })
+        .collect())
+}
+
+/// Validate a parsed config value against the schema.
+/// Checks for unknown keys, invalid channel names, unresolvable placeholders and
+/// non-existent paths.
+fn validate_value(value: &Value, resolver: &Resolver) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    let Some(obj) = value.as_object() else {
+        issues.push("config must be a JSON object".to_string());
+        return issues;
+    };
+
+    check_keys(obj, &config_schema(), "", &mut issues);
+    check_channel(obj, "", &mut issues);
+    check_enum(
+        obj,
+        "exit_code_mode",
+        VALID_EXIT_CODE_MODES,
+        "",
+        &mut issues,
+    );
+
+    if let Some(profiles) = obj.get("profiles").and_then(|v| v.as_object()) {
+        let schema = profile_schema();
+        for (name, profile) in profiles {
+            let prefix = format!("profiles.{name}.");
+            match profile.as_object() {
+                Some(profile) => {
+                    check_keys(profile, &schema, &prefix, &mut issues);
+                    check_channel(profile, &prefix, &mut issues);
+                }
+                None => issues.push(format!("profile '{name}' must be a JSON object")),
+            }
+        }
+    }
+
+    if let Some(commands) = obj.get("commands").and_then(|v| v.as_object()) {
+        let schema = command_schema();
+        for (name, command) in commands {
+            match command.as_object() {
+                Some(command) => {
+                    check_keys(command, &schema, &format!("commands.{name}."), &mut issues)
+                }
+                None => issues.push(format!("command '{name}' must be a JSON object")),
+            }
+        }
+    }
+
+    if let Some(agents) = obj.get("java_agents").and_then(|v| v.as_array()) {
+        let schema = java_agent_schema();
+        for (index, agent) in agents.iter().enumerate() {
+            let prefix = format!("java_agents[{index}]");
+            let Some(agent) = agent.as_object() else {
+                issues.push(format!("{prefix} must be a JSON object"));
+                continue;
+            };
+            check_keys(agent, &schema, &format!("{prefix}."), &mut issues);
+            let path = agent.get("path").and_then(|v| v.as_str());
+            match (path, agent.contains_key("id")) {
+                (Some(_), true) | (None, false) => {
+                    issues.push(format!("{prefix} needs exactly one of 'path' or 'id'"))
+                }
+                (None, true) if !agent.contains_key("version") => {
+                    issues.push(format!("{prefix} needs a 'version' with 'id'"))
+                }
+                (Some(path), false) => match resolver.path(path) {
+                    Ok(resolved) if !Path::new(&resolved).exists() => {
+                        issues.push(format!("{prefix}.path '{resolved}' does not exist"))
+                    }
+                    Ok(_) => {}
+                    Err(e) => issues.push(format!("{prefix}.path: {e}")),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    for key in ["jre_path", "dist_path"] {
+        if let Some(path) = obj.get(key).and_then(|v| v.as_str()) {
+            match resolver.path(path) {
+                Ok(resolved) if !Path::new(&resolved).exists() => {
+                    issues.push(format!("{key} '{resolved}' does not exist"))
+                }
+                Ok(_) => {}
+                Err(e) => issues.push(format!("{key}: {e}")),
+            }
+        }
+    }
+
+    if let Some(opts) = obj.get("jvm_opts").and_then(|v| v.as_str()) {
+        if let Err(e) = resolver.text(opts).and_then(|o| split_shell_words(&o)) {
+            issues.push(format!("jvm_opts: {e}"));
+        }
+    } else {
+        check_text(obj.get("jvm_opts"), "jvm_opts", resolver, &mut issues);
+    }
+    check_text(obj.get("env"), "env", resolver, &mut issues);
+    check_paths(obj.get("env_files"), "env_files", resolver, &mut issues);
+
+    // The same fields `try_resolve_values` resolves at load time, so a file
+    // that passes here doesn't fail every launch.
+    for (name, profile) in obj
+        .get("profiles")
+        .and_then(|v| v.as_object())
+        .into_iter()
+        .flatten()
+    {
+        let prefix = format!("profiles.{name}");
+        check_text(
+            profile.get("jvm_opts"),
+            &format!("{prefix}.jvm_opts"),
+            resolver,
+            &mut issues,
+        );
+        check_paths(
+            profile.get("classpath"),
+            &format!("{prefix}.classpath"),
+            resolver,
+            &mut issues,
+        );
+        check_text(
+            profile.get("env"),
+            &format!("{prefix}.env"),
+            resolver,
+            &mut issues,
+        );
+    }
+    for (name, command) in obj
+        .get("commands")
+        .and_then(|v| v.as_object())
+        .into_iter()
+        .flatten()
+    {
+        let prefix = format!("commands.{name}");
+        for key in ["jvm_opts", "system_properties", "args"] {
+            check_text(
+                command.get(key),
+                &format!("{prefix}.{key}"),
+                resolver,
+                &mut issues,
+            );
+        }
+    }
+    for (index, agent) in obj
+        .get("java_agents")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        check_text(
+            agent.get("options"),
+            &format!("java_agents[{index}].options"),
+            resolver,
+            &mut issues,
+        );
+    }
+
+    issues
+}
+
+/// Report unresolvable placeholders in a string, or in every string of an
+/// array or object value.
+fn check_text(value: Option<&Value>, key: &str, resolver: &Resolver, issues: &mut Vec<String>) {
+    check_strings(value, key, &|s| resolver.text(s), issues);
+}
+
+/// Like [`check_text`], expanding each string as a path.
+fn check_paths(value: Option<&Value>, key: &str, resolver: &Resolver, issues: &mut Vec<String>) {
+    check_strings(value, key, &|s| resolver.path(s), issues);
+}
+
+fn check_strings(
+    value: Option<&Value>,
+    key: &str,
+    resolve: &dyn Fn(&str) -> Result<String, String>,
+    issues: &mut Vec<String>,
+) {
+    match value {
+        Some(Value::String(s)) => {
+            if let Err(e) = resolve(s) {
+                issues.push(format!("{key}: {e}"));
+            }
+        }
+        Some(Value::Array(items)) => {
+            for (index, item) in items.iter().enumerate() {
+                check_strings(Some(item), &format!("{key}[{index}]"), resolve, issues);
+            }
+        }
+        Some(Value::Object(map)) => {
+            for (name, item) in map {
+                check_strings(Some(item), &format!("{key}.{name}"), resolve, issues);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Report keys not declared in `schema`'s properties.
+fn check_keys(
+    obj: &serde_json::Map<String, Value>,
+    schema: &Value,
+    prefix: &str,
+    issues: &mut Vec<String>,
+) {
+    let known: Vec<&str> = schema["properties"]
+        .as_object()
+        .map(|props| props.keys().map(|k| k.as_str()).collect())
+        .unwrap_or_default();
+
+    for key in obj.keys() {
+        if known.contains(&key.as_str()) {
+            continue;
+        }
+        match suggest_key(key, &known) {
+            Some(suggestion) => issues.push(format!(
+                "unknown key '{prefix}{key}' (did you mean '{prefix}{suggestion}'?)"
+            )),
+            None => issues.push(format!("unknown key '{prefix}{key}'")),
+        }
+    }
+}
+
+/// Report a `channel` value that isn't a known release channel.
+fn check_channel(obj: &serde_json::Map<String, Value>, prefix: &str, issues: &mut Vec<String>) {
+    check_enum(obj, "channel", VALID_CHANNELS, prefix, issues);
+}
+
+/// Report a string value that isn't one of the allowed values.
+fn check_enum(
+    obj: &serde_json::Map<String, Value>,
+    key: &str,
+    valid: &[&str],
+    prefix: &str,
+    issues: &mut Vec<String>,
+) {
+    if let Some(value) = obj.get(key).and_then(|v| v.as_str()) {
+        if !valid.contains(&value) {
+            issues.push(format!(
+                "invalid {prefix}{key} '{value}' (expected one of: {})",
+                valid.join(", ")
+            ));
+        }
+    }
+}
+
+/// Suggest a known key for a likely typo (case, separators, or a small edit distance).
+fn suggest_key<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    let normalize = |s: &str| s.to_lowercase().replace(['_', '-'], "");
+    let key_norm = normalize(key);
+
+    known
+        .iter()
+        .map(|k| (*k, edit_distance(&key_norm, &normalize(k))))
+        .filter(|(_, d)| *d <= 2)
+        .min_by_key(|(_, d)| *d)
+        .map(|(k, _)| k)
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+
+    prev[b.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// An absolute project directory on any platform.
+    fn repo_dir() -> PathBuf {
+        if cfg!(windows) {
+            PathBuf::from(r"C:\repo")
+        } else {
+            PathBuf::from("/repo")
+        }
+    }
+
+    /// A `/`-separated path inside [`repo_dir`], as `Resolver::path` renders it.
+    fn in_repo(path: &str) -> String {
+        path.split('/')
+            .fold(repo_dir(), |dir, part| dir.join(part))
+            .display()
+            .to_string()
+    }
+
+    fn test_resolver() -> Resolver {
+        Resolver::new(&repo_dir(), &repo_dir())
+    }
+
     #[test]
     fn test_default_config() {
         let config = Config::default();
@@ -155,8 +1202,9 @@ mod tests {
             karate_version: "2.0.0".to_string(),
             jre_path: Some("/custom/jre".to_string()),
             dist_path: Some("/custom/dist".to_string()),
-            jvm_opts: Some("-Xmx1g".to_string()),
+            jvm_opts: Some(JvmOpts::Line("-Xmx1g".to_string())),
             check_updates: false,
+            ..Config::default()
         };
 
         base.merge(&override_config);
@@ -165,7 +1213,338 @@ mod tests {
         assert_eq!(base.karate_version, "2.0.0");
         assert_eq!(base.jre_path, Some("/custom/jre".to_string()));
         assert_eq!(base.dist_path, Some("/custom/dist".to_string()));
-        assert_eq!(base.jvm_opts, Some("-Xmx1g".to_string()));
+        assert_eq!(base.jvm_opts, Some(JvmOpts::Line("-Xmx1g".to_string())));
         assert!(!base.check_updates);
     }
+
+    #[test]
+    fn test_validate_unknown_keys_suggest_fix() {
+        let issues = validate_value(
+            &json!({"jvmOpts": "-Xmx1g", "check_update": false}),
+            &test_resolver(),
+        );
+        assert_eq!(issues.len(), 2);
+        assert!(
+            issues[0].contains("did you mean 'check_updates'"),
+            "{issues:?}"
+        );
+        assert!(issues[1].contains("did you mean 'jvm_opts'"), "{issues:?}");
+    }
+
+    #[test]
+    fn test_unknown_channel_is_a_config_error() {
+        assert!(validate_channel("beta").is_ok());
+        let err = anyhow::Error::from(validate_channel("edge").unwrap_err());
+        let report = crate::error::ErrorReport::new(&err);
+        assert_eq!(i32::from(report.exit_code), 2);
+        assert!(
+            report.message.contains("Unknown channel 'edge'"),
+            "{}",
+            report.message
+        );
+    }
+
+    #[test]
+    fn test_validate_channel_and_paths() {
+        let issues = validate_value(
+            &json!({
+                "$schema": "./karate-cli.schema.json",
+                "channel": "nightlyy",
+                "exit_code_mode": "signed",
+                "jre_path": "/does/not/exist"
+            }),
+            &test_resolver(),
+        );
+        assert_eq!(issues.len(), 3, "{issues:?}");
+        assert!(issues[0].contains("invalid channel 'nightlyy'"));
+        assert!(issues[1].contains("invalid exit_code_mode 'signed'"));
+        assert!(issues[2].contains("jre_path"));
+    }
+
+    #[test]
+    fn test_schema_covers_config_fields() {
+        let schema = config_schema();
+        let config = serde_json::to_value(Config {
+            jre_path: Some("x".into()),
+            dist_path: Some("x".into()),
+            jvm_opts: Some(JvmOpts::Line("x".into())),
+            cds: Some(false),
+            exit_code_mode: Some(ExitCodeMode::Raw),
+            ..Config::default()
+        })
+        .unwrap();
+        for key in config.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(key).is_some(), "missing {key}");
+        }
+    }
+
+    #[test]
+    fn test_validate_java_agents() {
+        let missing = in_repo("no/such/agent.jar");
+        let value = serde_json::json!({
+            "java_agents": [
+                { "id": "jacoco", "version": "0.8.12", "options": "destfile=${project_dir}/jacoco.exec" },
+                { "id": "jacoco" },
+                { "path": missing, "id": "jacoco" },
+                { "path": missing, "option": "x" }
+            ]
+        });
+        let issues = validate_value(&value, &test_resolver());
+        assert_eq!(
+            issues,
+            vec![
+                "java_agents[1] needs a 'version' with 'id'",
+                "java_agents[2] needs exactly one of 'path' or 'id'",
+                "unknown key 'java_agents[3].option' (did you mean 'java_agents[3].options'?)",
+                format!("java_agents[3].path '{missing}' does not exist").as_str(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profile_applies_on_top() {
+        let mut config: Config = serde_json::from_value(json!({
+            "jvm_opts": "-Xmx512m",
+            "profiles": {
+                "ci": {
+                    "jvm_opts": "-Xmx4g",
+                    "channel": "beta",
+                    "classpath": ["lib/ci.jar"],
+                    "env": { "CI": "true" }
+                }
+            }
+        }))
+        .unwrap();
+
+        config.resolve_profile(Some("ci")).unwrap();
+
+        assert_eq!(config.profile.as_deref(), Some("ci"));
+        assert_eq!(config.jvm_opts, Some(JvmOpts::Line("-Xmx4g".into())));
+        assert_eq!(config.channel, "beta");
+        assert_eq!(config.classpath, vec!["lib/ci.jar"]);
+        assert_eq!(config.env.get("CI").map(|s| s.as_str()), Some("true"));
+    }
+
+    #[test]
+    fn test_unknown_profile_is_an_error() {
+        let mut config: Config =
+            serde_json::from_value(json!({ "profiles": { "ci": {}, "perf": {} } })).unwrap();
+        let err = config
+            .resolve_profile(Some("laptop"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Unknown profile 'laptop'"), "{err}");
+        assert!(err.contains("ci, perf"), "{err}");
+    }
+
+    #[test]
+    fn test_selection_overrides_default_profile() {
+        let mut config: Config = serde_json::from_value(json!({
+            "profile": "ci",
+            "env_files": [in_repo(".env")],
+            "profiles": { "ci": { "channel": "beta" }, "perf": { "channel": "nightly" } }
+        }))
+        .unwrap();
+        let selection = ConfigSelection {
+            profile: Some("perf".into()),
+            env_files: vec!["local.env".into()],
+        };
+
+        config
+            .apply_selection(&selection, &test_resolver())
+            .unwrap();
+
+        assert_eq!(config.profile.as_deref(), Some("perf"));
+        assert_eq!(config.channel, "nightly");
+        assert_eq!(
+            config.env_files,
+            vec![in_repo(".env"), in_repo("local.env")]
+        );
+    }
+
+    #[test]
+    fn test_top_level_classpath_is_not_a_config_key() {
+        let issues = validate_value(&json!({ "classpath": ["a.jar"] }), &test_resolver());
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert!(issues[0].contains("'classpath'"), "{issues:?}");
+    }
+
+    #[test]
+    fn test_validate_profile_keys() {
+        let issues = validate_value(
+            &json!({
+                "profiles": { "ci": { "jvmOpts": "-Xmx4g", "channel": "edge" } }
+            }),
+            &test_resolver(),
+        );
+        assert_eq!(issues.len(), 2, "{issues:?}");
+        assert!(issues[0].contains("'profiles.ci.jvm_opts'"), "{issues:?}");
+        assert!(
+            issues[1].contains("invalid profiles.ci.channel"),
+            "{issues:?}"
+        );
+    }
+
+    #[test]
+    fn test_validate_profile_placeholders() {
+        let issues = validate_value(
+            &json!({
+                "jvm_opts": ["-Dok=1"],
+                "profiles": { "ci": { "env": { "TOKEN": "${KARATE_CLI_TEST_UNDEFINED}" } } }
+            }),
+            &test_resolver(),
+        );
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert!(
+            issues[0].starts_with("profiles.ci.env.TOKEN: "),
+            "{issues:?}"
+        );
+        assert!(issues[0].contains("is not defined"), "{issues:?}");
+    }
+
+    #[test]
+    fn test_resolve_relative_and_home_paths() {
+        let resolver = test_resolver();
+        assert_eq!(
+            resolver.path("./tools/karate").unwrap(),
+            in_repo("tools/karate")
+        );
+        let absolute = in_repo("opt/jre");
+        assert_eq!(resolver.path(&absolute).unwrap(), absolute);
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            resolver.path("~/certs").unwrap(),
+            home.join("certs").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_interpolate_placeholders() {
+        let resolver = test_resolver();
+        // Cargo sets CARGO_PKG_NAME for test binaries
+        assert_eq!(
+            resolver.text("-Dname=${env:CARGO_PKG_NAME}").unwrap(),
+            "-Dname=karate-cli"
+        );
+        assert_eq!(
+            resolver.text("${project_dir}/certs.jks").unwrap(),
+            format!("{}/certs.jks", repo_dir().display())
+        );
+        assert_eq!(resolver.text("-Dx=$${literal}").unwrap(), "-Dx=${literal}");
+    }
+
+    #[test]
+    fn test_undefined_placeholders_are_errors() {
+        let resolver = test_resolver();
+        let err = resolver
+            .text("${env:KARATE_CLI_TEST_UNDEFINED}")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("'KARATE_CLI_TEST_UNDEFINED' is not defined"),
+            "{err}"
+        );
+
+        let err = resolver
+            .text("${KARATE_CLI_TEST_UNDEFINED}/certs")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("'KARATE_CLI_TEST_UNDEFINED' is not defined"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_redacted_hides_env_values() {
+        let config: Config = serde_json::from_value(json!({
+            "env": { "API_TOKEN": "secret" },
+            "profiles": { "ci": { "env": { "CI_TOKEN": "secret" } } }
+        }))
+        .unwrap();
+        let shown = serde_json::to_string(&config.redacted()).unwrap();
+        assert!(!shown.contains("secret"), "{shown}");
+        assert!(shown.contains(r#""API_TOKEN":"***""#), "{shown}");
+        assert!(shown.contains(r#""CI_TOKEN":"***""#), "{shown}");
+    }
+
+    #[test]
+    fn test_bare_placeholders_read_the_environment() {
+        let resolver = test_resolver();
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(
+            resolver.text("${PATH}/certs").unwrap(),
+            format!("{path}/certs")
+        );
+        assert_eq!(
+            resolver.text("${env:PATH}").unwrap(),
+            resolver.text("${PATH}").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_jvm_opts_shell_quoting() {
+        let opts = JvmOpts::Line(
+            r#"-Xmx1g -Dkarate.options="--tags @smoke" '-Dpath=/my dir/x' -Dq=a\ b"#.into(),
+        );
+        assert_eq!(
+            opts.args().unwrap(),
+            vec![
+                "-Xmx1g",
+                "-Dkarate.options=--tags @smoke",
+                "-Dpath=/my dir/x",
+                "-Dq=a b"
+            ]
+        );
+        assert!(opts.has_quotes());
+    }
+
+    #[test]
+    fn test_jvm_opts_keeps_windows_paths() {
+        let opts = JvmOpts::Line(r#"-Djava.io.tmpdir=C:\temp "-Dx=C:\Program Files\x""#.into());
+        assert_eq!(
+            opts.args().unwrap(),
+            vec![r"-Djava.io.tmpdir=C:\temp", r"-Dx=C:\Program Files\x"]
+        );
+    }
+
+    #[test]
+    fn test_jvm_opts_array_form() {
+        let config: Config =
+            serde_json::from_value(json!({ "jvm_opts": ["-Dkarate.options=--tags @smoke"] }))
+                .unwrap();
+        let opts = config.jvm_opts.unwrap();
+        assert_eq!(opts.args().unwrap(), vec!["-Dkarate.options=--tags @smoke"]);
+        assert!(!opts.has_quotes());
+    }
+
+    #[test]
+    fn test_jvm_opts_unterminated_quote() {
+        let err = JvmOpts::Line(r#"-Dx="oops"#.into())
+            .args()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unterminated double quote"), "{err}");
+    }
+
+    #[test]
+    fn test_command_jvm_args() {
+        let config: Config = serde_json::from_value(json!({
+            "commands": {
+                "mock": {
+                    "jvm_opts": "-Xmx256m",
+                    "system_properties": { "karate.env": "mock", "a.b": "x y" },
+                    "args": ["-p", "8080"]
+                }
+            }
+        }))
+        .unwrap();
+
+        let mock = &config.commands["mock"];
+        assert_eq!(
+            mock.jvm_args().unwrap(),
+            vec!["-Xmx256m", "-Da.b=x y", "-Dkarate.env=mock"]
+        );
+        assert_eq!(mock.args, vec!["-p", "8080"]);
+    }
 }
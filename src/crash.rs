@@ -0,0 +1,497 @@
+//! JVM crash diagnosis - explain why a delegated JVM died.
+//!
+//! After a delegated JVM fails, the launcher looks for HotSpot crash logs
+//! (`hs_err_pid*.log`) and heap dumps (`java_pid*.hprof`) that appeared during the
+//! run, a terminating signal, and `OutOfMemoryError` (reported by an
+//! `-XX:OnOutOfMemoryError` hook that touches a marker file, or found in the
+//! --log-file output), then prints what happened and what to change to stderr.
+
+use crate::jre::{java_home, parse_java_version};
+use crate::platform::KaratePaths;
+use crate::resources::ContainerLimits;
+use console::style;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::SystemTime;
+
+/// Heap size suggested when no `-Xmx` is set.
+const DEFAULT_HEAP_SUGGESTION: &str = "-Xmx2g";
+
+/// Signals that mean the JVM itself crashed (it aborts after writing hs_err).
+const CRASH_SIGNALS: &[i32] = &[4, 6, 7, 8, 11];
+
+/// Crash logs and heap dumps present before a run, so only new ones are reported,
+/// and the marker file the JVM touches on `OutOfMemoryError`.
+pub struct Artifacts {
+    started: SystemTime,
+    dirs: Vec<PathBuf>,
+    existing: HashSet<PathBuf>,
+    oom_marker: Option<PathBuf>,
+}
+
+/// Where a JVM started by this launcher touches its marker on `OutOfMemoryError`;
+/// `None` when `jvm_args` has its own hook (the JVM only runs the last one).
+pub fn oom_marker(paths: &KaratePaths, jvm_args: &[String]) -> Option<PathBuf> {
+    (!jvm_args
+        .iter()
+        .any(|a| a.starts_with("-XX:OnOutOfMemoryError=")))
+    .then(|| {
+        paths
+            .cache
+            .join("oom")
+            .join(format!("{}.marker", std::process::id()))
+    })
+}
+
+/// JVM option that touches `marker` on `OutOfMemoryError`, so it can be detected
+/// without capturing the JVM's output.
+pub fn oom_hook_arg(marker: &Path) -> String {
+    let marker = marker.display().to_string();
+    let command = if cfg!(windows) {
+        format!("type nul > \"{marker}\"")
+    } else {
+        format!("touch '{}'", marker.replace('\'', r"'\''"))
+    };
+    format!("-XX:OnOutOfMemoryError={command}")
+}
+
+/// Clear a marker left by an earlier JVM and make sure the hook can create it.
+pub fn prepare_oom_marker(marker: &Path) {
+    let _ = std::fs::remove_file(marker);
+    if let Some(dir) = marker.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+}
+
+impl Artifacts {
+    /// Record the files in the working directory (and `-XX:HeapDumpPath`, if it's a
+    /// directory) before launching a JVM that touches `oom_marker` on
+    /// `OutOfMemoryError`.
+    pub fn snapshot(jvm_args: &[String], oom_marker: Option<&Path>) -> Self {
+        let mut dirs = vec![std::env::current_dir().unwrap_or_default()];
+        let dump_dir = jvm_args
+            .iter()
+            .rev()
+            .find_map(|arg| arg.strip_prefix("-XX:HeapDumpPath="))
+            .map(PathBuf::from)
+            .filter(|path| path.is_dir());
+        dirs.extend(dump_dir);
+
+        if let Some(marker) = oom_marker {
+            prepare_oom_marker(marker);
+        }
+
+        let mut artifacts = Artifacts {
+            started: SystemTime::now(),
+            dirs,
+            existing: HashSet::new(),
+            oom_marker: oom_marker.map(Path::to_path_buf),
+        };
+        artifacts.existing = artifacts.files().into_iter().collect();
+        artifacts
+    }
+
+    /// Whether the JVM reported `OutOfMemoryError`; removes the marker, so a later
+    /// JVM from this launcher can't be blamed for it.
+    pub fn take_oom_marker(&self) -> bool {
+        self.oom_marker
+            .as_ref()
+            .is_some_and(|marker| std::fs::remove_file(marker).is_ok())
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        self.dirs
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| is_crash_log(path) || is_heap_dump(path))
+            .collect()
+    }
+
+    /// Crash logs and heap dumps written since the snapshot.
+    fn new_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self
+            .files()
+            .into_iter()
+            .filter(|path| {
+                let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+                !self.existing.contains(path) || modified.is_some_and(|m| m >= self.started)
+            })
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn is_crash_log(path: &Path) -> bool {
+    let name = file_name(path);
+    name.starts_with("hs_err_pid") && name.ends_with(".log")
+}
+
+fn is_heap_dump(path: &Path) -> bool {
+    let name = file_name(path);
+    name.starts_with("java_pid") && name.ends_with(".hprof")
+}
+
+/// What the launcher observed about a finished JVM.
+#[derive(Debug, Default)]
+pub struct Evidence {
+    pub signal: Option<i32>,
+    /// A new crash log and its contents
+    pub crash_log: Option<(PathBuf, String)>,
+    pub heap_dump: Option<PathBuf>,
+    /// The OnOutOfMemoryError hook ran
+    pub oom_reported: bool,
+    /// Captured output (from --log-file)
+    pub output: Option<String>,
+}
+
+impl Evidence {
+    /// Gather evidence after a run from its exit status, new files and log file.
+    pub fn collect(artifacts: &Artifacts, status: &ExitStatus, log_file: Option<&Path>) -> Self {
+        let new_files = artifacts.new_files();
+        let crash_log = new_files
+            .iter()
+            .find(|path| is_crash_log(path))
+            .map(|path| {
+                (
+                    path.clone(),
+                    std::fs::read_to_string(path).unwrap_or_default(),
+                )
+            });
+        Evidence {
+            signal: exit_signal(status),
+            crash_log,
+            heap_dump: new_files.into_iter().find(|path| is_heap_dump(path)),
+            oom_reported: artifacts.take_oom_marker(),
+            output: log_file.and_then(|path| std::fs::read_to_string(path).ok()),
+        }
+    }
+}
+
+/// The signal that killed a process, if any.
+#[cfg(unix)]
+pub fn exit_signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+pub fn exit_signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Why the JVM died.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashKind {
+    OutOfMemory,
+    Crashed,
+    /// SIGKILL from outside, usually the kernel's out-of-memory killer
+    Killed,
+}
+
+#[derive(Debug)]
+pub struct Diagnosis {
+    pub kind: CrashKind,
+    pub signal: Option<i32>,
+    pub crash_log: Option<PathBuf>,
+    pub heap_dump: Option<PathBuf>,
+    /// OutOfMemoryError message or the crash log's problematic frame
+    pub detail: Option<String>,
+}
+
+/// Explain an abnormal JVM exit; `None` for ordinary exits such as failed tests.
+pub fn diagnose(evidence: &Evidence) -> Option<Diagnosis> {
+    let crash_text = evidence.crash_log.as_ref().map(|(_, text)| text.as_str());
+    // The exit code alone says nothing: Karate exits with its failure count, which can
+    // be 3 just like -XX:+ExitOnOutOfMemoryError (whose hook still touches the marker)
+    let oom_message = evidence
+        .output
+        .as_deref()
+        .and_then(jvm_oom_in_output)
+        .or_else(|| crash_text.and_then(oom_message));
+    let crash_oom = crash_text.is_some_and(|t| t.contains("Out of Memory Error"));
+
+    let (kind, detail) = if oom_message.is_some()
+        || evidence.oom_reported
+        || crash_oom
+        || evidence.heap_dump.is_some()
+    {
+        (CrashKind::OutOfMemory, oom_message)
+    } else if evidence.crash_log.is_some()
+        || evidence.signal.is_some_and(|s| CRASH_SIGNALS.contains(&s))
+    {
+        (CrashKind::Crashed, crash_text.and_then(problematic_frame))
+    } else if evidence.signal == Some(9) {
+        (CrashKind::Killed, None)
+    } else {
+        return None;
+    };
+
+    Some(Diagnosis {
+        kind,
+        signal: evidence.signal,
+        crash_log: evidence.crash_log.as_ref().map(|(path, _)| path.clone()),
+        heap_dump: evidence.heap_dump.clone(),
+        detail,
+    })
+}
+
+/// The message of the first `java.lang.OutOfMemoryError`, e.g. `Java heap space`.
+/// An `OutOfMemoryError` the JVM itself reported on stderr, either uncaught
+/// (`Exception in thread ...`) or from `-XX:+ExitOnOutOfMemoryError`
+/// (`Terminating due to ...`). Tests that merely log the class name don't count.
+fn jvm_oom_in_output(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter(|line| {
+            line.starts_with("Exception in thread ") || line.starts_with("Terminating due to ")
+        })
+        .find_map(oom_message)
+}
+
+fn oom_message(text: &str) -> Option<String> {
+    let start = text.find("java.lang.OutOfMemoryError")?;
+    let line = text[start..].lines().next().unwrap_or_default();
+    Some(
+        line.split_once(": ")
+            .map(|(_, message)| message.trim().to_string())
+            .unwrap_or_else(|| "OutOfMemoryError".to_string()),
+    )
+}
+
+/// The frame HotSpot blames, from the line after `# Problematic frame:`.
+fn problematic_frame(text: &str) -> Option<String> {
+    let mut lines = text.lines();
+    lines.find(|line| line.starts_with("# Problematic frame:"))?;
+    let frame = lines.next()?.trim_start_matches('#').trim();
+    (!frame.is_empty()).then(|| frame.to_string())
+}
+
+/// The effective `-Xmx`, if set.
+fn max_heap(jvm_args: &[String]) -> Option<&str> {
+    jvm_args
+        .iter()
+        .rev()
+        .find_map(|arg| arg.strip_prefix("-Xmx"))
+}
+
+/// A larger heap setting: double the current `-Xmx`, or a fixed default.
+fn suggest_heap(jvm_args: &[String]) -> String {
+    let Some(current) = max_heap(jvm_args) else {
+        return DEFAULT_HEAP_SUGGESTION.to_string();
+    };
+    let split = current
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(current.len());
+    let (number, unit) = current.split_at(split);
+    let Ok(number) = number.parse::<u64>() else {
+        return DEFAULT_HEAP_SUGGESTION.to_string();
+    };
+    let Some(doubled) = number.checked_mul(2) else {
+        return DEFAULT_HEAP_SUGGESTION.to_string();
+    };
+    match unit.to_ascii_lowercase().as_str() {
+        "m" if doubled.is_multiple_of(1024) => format!("-Xmx{}g", doubled / 1024),
+        _ => format!("-Xmx{doubled}{unit}"),
+    }
+}
+
+fn signal_name(signal: i32) -> String {
+    match signal {
+        4 => "SIGILL".to_string(),
+        6 => "SIGABRT".to_string(),
+        7 => "SIGBUS".to_string(),
+        8 => "SIGFPE".to_string(),
+        9 => "SIGKILL".to_string(),
+        11 => "SIGSEGV".to_string(),
+        15 => "SIGTERM".to_string(),
+        _ => format!("signal {signal}"),
+    }
+}
+
+/// Print a diagnosis with the JVM's heap setting, JRE and a suggested fix.
+pub fn print_diagnosis(diagnosis: &Diagnosis, java: &Path, jvm_args: &[String]) {
+    let headline = match diagnosis.kind {
+        CrashKind::OutOfMemory => match &diagnosis.detail {
+            Some(message) => format!("The JVM ran out of memory ({message})"),
+            None => "The JVM ran out of memory".to_string(),
+        },
+        CrashKind::Crashed => match diagnosis.signal {
+            Some(signal) => format!("The JVM crashed ({})", signal_name(signal)),
+            None => "The JVM crashed".to_string(),
+        },
+        CrashKind::Killed => "The JVM was killed (SIGKILL)".to_string(),
+    };
+    eprintln!();
+    eprintln!(
+        "{} {}",
+        style("✗").red().bold(),
+        style(headline).red().bold()
+    );
+
+    if let Some(path) = &diagnosis.crash_log {
+        eprintln!("  Crash log:    {}", path.display());
+    }
+    if let Some(path) = &diagnosis.heap_dump {
+        eprintln!("  Heap dump:    {}", path.display());
+    }
+    if diagnosis.kind == CrashKind::Crashed {
+        if let Some(frame) = &diagnosis.detail {
+            eprintln!("  Frame:        {}", frame);
+        }
+    }
+    if diagnosis.kind != CrashKind::Crashed {
+        let heap = max_heap(jvm_args)
+            .map(|xmx| format!("-Xmx{xmx}"))
+            .or_else(|| {
+                jvm_args
+                    .iter()
+                    .find(|a| a.starts_with("-XX:MaxRAMPercentage="))
+                    .cloned()
+            })
+            .unwrap_or_else(|| "JVM default (25% of RAM)".to_string());
+        eprintln!("  Max heap:     {}", heap);
+        if let Some(bytes) = ContainerLimits::detect().memory_bytes {
+            eprintln!(
+                "  Memory limit: {:.1} GiB (cgroup)",
+                bytes as f64 / (1u64 << 30) as f64
+            );
+        }
+    }
+    let home = java_home(java).unwrap_or_else(|| java.display().to_string());
+    match parse_java_version(java) {
+        Some((version, _)) => eprintln!("  JRE:          {} ({})", version, home),
+        None => eprintln!("  JRE:          {}", home),
+    }
+
+    let suggestion = match diagnosis.kind {
+        CrashKind::OutOfMemory => format!(
+            "Raise the heap in config, e.g. \"jvm_opts\": \"{}\" (karate config --global)",
+            suggest_heap(jvm_args)
+        ),
+        CrashKind::Killed => "This is usually the system's out-of-memory killer: lower the heap in jvm_opts or raise the machine/container memory".to_string(),
+        CrashKind::Crashed => "Native crashes are JVM or native library bugs: try another JRE (karate jre list) and attach the crash log to a bug report".to_string(),
+    };
+    eprintln!("  {} {}", style("→").cyan(), suggestion);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn oom_marker_is_cleared_after_a_clean_run() {
+        let home = tempfile::tempdir().unwrap();
+        let paths = KaratePaths::in_home(home.path());
+        assert!(oom_marker(&paths, &args(&["-XX:OnOutOfMemoryError=kill -9 %p"])).is_none());
+
+        let marker = oom_marker(&paths, &args(&["-Xmx1g"])).unwrap();
+        assert!(oom_hook_arg(&marker).starts_with("-XX:OnOutOfMemoryError="));
+
+        // A marker left behind is cleared before the next JVM starts
+        std::fs::create_dir_all(marker.parent().unwrap()).unwrap();
+        std::fs::write(&marker, "").unwrap();
+        let artifacts = Artifacts::snapshot(&[], Some(&marker));
+        assert!(!marker.exists());
+
+        std::fs::write(&marker, "").unwrap();
+        assert!(artifacts.take_oom_marker());
+        assert!(!marker.exists());
+        assert!(!artifacts.take_oom_marker());
+    }
+
+    #[test]
+    fn detects_out_of_memory() {
+        let evidence = Evidence {
+            output: Some(
+                "Exception in thread \"main\" java.lang.OutOfMemoryError: Java heap space\n\tat x"
+                    .to_string(),
+            ),
+            ..Evidence::default()
+        };
+        let diagnosis = diagnose(&evidence).unwrap();
+        assert_eq!(diagnosis.kind, CrashKind::OutOfMemory);
+        assert_eq!(diagnosis.detail.as_deref(), Some("Java heap space"));
+
+        let hook = Evidence {
+            oom_reported: true,
+            ..Evidence::default()
+        };
+        assert_eq!(
+            diagnose(&hook).map(|d| d.kind),
+            Some(CrashKind::OutOfMemory)
+        );
+
+        let exit_on_oom = Evidence {
+            output: Some("Terminating due to java.lang.OutOfMemoryError: Java heap space\n".into()),
+            ..Evidence::default()
+        };
+        assert_eq!(
+            diagnose(&exit_on_oom).map(|d| d.kind),
+            Some(CrashKind::OutOfMemory)
+        );
+    }
+
+    #[test]
+    fn ignores_oom_mentioned_by_tests() {
+        let logged = Evidence {
+            output: Some(
+                "12:00:01 INFO  expected java.lang.OutOfMemoryError: Java heap space\n".into(),
+            ),
+            ..Evidence::default()
+        };
+        assert!(diagnose(&logged).is_none());
+    }
+
+    #[test]
+    fn detects_native_crash_and_kill() {
+        let crash_log = "#\n# A fatal error has been detected by the Java Runtime Environment:\n#\n# Problematic frame:\n# C  [libnative.so+0x1a2b]  crash+0x10\n#\n";
+        let evidence = Evidence {
+            signal: Some(6),
+            crash_log: Some((PathBuf::from("hs_err_pid42.log"), crash_log.to_string())),
+            ..Evidence::default()
+        };
+        let diagnosis = diagnose(&evidence).unwrap();
+        assert_eq!(diagnosis.kind, CrashKind::Crashed);
+        assert_eq!(
+            diagnosis.detail.as_deref(),
+            Some("C  [libnative.so+0x1a2b]  crash+0x10")
+        );
+
+        let killed = Evidence {
+            signal: Some(9),
+            ..Evidence::default()
+        };
+        assert_eq!(diagnose(&killed).map(|d| d.kind), Some(CrashKind::Killed));
+
+        let failed_tests = Evidence {
+            ..Evidence::default()
+        };
+        assert!(diagnose(&failed_tests).is_none());
+    }
+
+    #[test]
+    fn suggests_a_larger_heap() {
+        assert_eq!(suggest_heap(&args(&["-Xmx512m"])), "-Xmx1g");
+        assert_eq!(suggest_heap(&args(&["-Xmx1g", "-Xmx3g"])), "-Xmx6g");
+        assert_eq!(suggest_heap(&args(&["-Xmx700m"])), "-Xmx1400m");
+        assert_eq!(suggest_heap(&[]), "-Xmx2g");
+        assert_eq!(
+            suggest_heap(&args(&["-Xmx18446744073709551615k"])),
+            "-Xmx2g"
+        );
+    }
+}
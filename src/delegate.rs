@@ -1,67 +1,650 @@
 //! JAR delegation - pass commands through to the Karate JAR via JVM.
 
-use crate::config::load_merged_config;
+use crate::agents::{agent_args, ensure_agents};
+use crate::cds::shared_archive_arg;
+use crate::cli::OutputFormat;
+use crate::config::{
+    load_merged_config, validate_config_files, CommandConfig, Config, ConfigSelection, ExitCodeMode,
+};
+use crate::crash::{
+    diagnose, exit_signal, oom_hook_arg, oom_marker, print_diagnosis, Artifacts, Evidence,
+};
+use crate::env::{resolve_env, EnvVar, REDACTED};
 use crate::error::{ExitCode, KarateError};
+use crate::history::record_run;
 use crate::jre::find_active_jre;
 use crate::platform::KaratePaths;
+use crate::process::{format_duration, run_supervised, Outcome, Supervision};
+use crate::resources::JvmResources;
+use crate::shard::{run_sharded, take_shard_options};
 use anyhow::{Context, Result};
+use console::style;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::ExitStatus;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::process::Command;
+use tracing::debug;
+
+/// Karate's main class.
+const MAIN_CLASS: &str = "com.intuit.karate.Main";
+
+/// Total length of JVM arguments above which they are passed via an `@argfile`.
+/// Windows limits the whole command line to 32K (8K via cmd.exe), and Linux limits a
+/// single argument — such as a long classpath — to 128K.
+const ARGFILE_THRESHOLD: usize = 8000;
+
+/// Argfiles not written for this long are removed when another one is written.
+const ARGFILE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Interface the JDWP agent listens on; never exposed beyond localhost.
+const DEBUG_HOST: &str = "127.0.0.1";
+
+/// Launcher options from global CLI flags that apply to delegated commands.
+#[derive(Debug, Default)]
+pub struct LaunchOptions {
+    /// Additional classpath entries (--cp)
+    pub extra_classpath: Vec<String>,
+    /// Print the resolved command instead of launching it (--dry-run)
+    pub dry_run: Option<OutputFormat>,
+    /// JDWP port for remote debugging (--debug-jvm)
+    pub debug_port: Option<u16>,
+    /// Wait for a debugger before running (--debug-suspend)
+    pub debug_suspend: bool,
+    /// Timeout and log file (--timeout, --log-file)
+    pub supervision: Supervision,
+    /// Profile and env files chosen on the command line (--profile, --env-file)
+    pub config: ConfigSelection,
+}
+
+/// Where a classpath entry came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClasspathSource {
+    /// The Karate JAR from dist/
+    Dist,
+    /// Global ext/ directory
+    GlobalExt,
+    /// Project .karate/ext/ directory
+    LocalExt,
+    /// `classpath` in the selected profile
+    Profile,
+    /// --cp flag
+    Cli,
+}
+
+impl std::fmt::Display for ClasspathSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClasspathSource::Dist => write!(f, "dist"),
+            ClasspathSource::GlobalExt => write!(f, "global ext"),
+            ClasspathSource::LocalExt => write!(f, "local ext"),
+            ClasspathSource::Profile => write!(f, "profile"),
+            ClasspathSource::Cli => write!(f, "--cp"),
+        }
+    }
+}
+
+/// A classpath entry and where it came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClasspathEntry {
+    pub path: String,
+    pub source: ClasspathSource,
+}
+
+/// Everything needed to launch a delegated command, resolved from config and flags.
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchPlan {
+    pub java: PathBuf,
+    pub jar: PathBuf,
+    /// JVM options (global, then per-command)
+    pub jvm_args: Vec<String>,
+    pub classpath: Vec<ClasspathEntry>,
+    pub main_class: String,
+    /// Arguments for Karate's Main
+    pub args: Vec<String>,
+    /// Environment for the java process (not serialized: values may be secrets)
+    #[serde(skip)]
+    pub env: BTreeMap<String, EnvVar>,
+    /// File the JVM's `-XX:OnOutOfMemoryError` hook touches, if the launcher added one
+    #[serde(skip)]
+    pub oom_marker: Option<PathBuf>,
+}
+
+impl LaunchPlan {
+    /// The classpath joined with the platform separator.
+    pub fn classpath_string(&self) -> String {
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        self.classpath
+            .iter()
+            .map(|e| e.path.as_str())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Arguments after the java executable, and the argfile if one is needed. The
+    /// argfile isn't written here; launching callers write it first.
+    ///
+    /// Long command lines go through an argfile so we don't hit OS limits. User
+    /// arguments always stay on the command line: the java launcher doesn't expand
+    /// @files after the main class.
+    pub fn command_args(&self, cache_dir: &Path) -> (Vec<String>, Option<Argfile>) {
+        let mut launcher_args = self.jvm_args.clone();
+        launcher_args.extend([
+            "-cp".to_string(),
+            self.classpath_string(),
+            self.main_class.clone(),
+        ]);
+
+        let mut argfile = None;
+        if launcher_args.iter().map(|a| a.len() + 1).sum::<usize>() > ARGFILE_THRESHOLD {
+            let file = Argfile::new(cache_dir, &launcher_args);
+            launcher_args = vec![format!("@{}", file.path.display())];
+            argfile = Some(file);
+        }
+
+        launcher_args.extend(self.args.iter().cloned());
+        (launcher_args, argfile)
+    }
+}
+
+/// How a delegated command finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// The JVM exited with this code
+    Exited(i32),
+    /// The JVM was killed by this signal
+    Signaled(i32),
+    /// Stopped by --timeout
+    TimedOut,
+    /// --dry-run printed the command instead
+    DryRun,
+}
+
+impl RunOutcome {
+    /// The outcome of a finished JVM process.
+    pub fn from_status(status: &ExitStatus) -> Self {
+        match (status.code(), exit_signal(status)) {
+            (Some(code), _) => RunOutcome::Exited(code),
+            (None, Some(signal)) => RunOutcome::Signaled(signal),
+            (None, None) => RunOutcome::Exited(1),
+        }
+    }
+}
 
 /// Run a delegated command through the JVM.
-pub async fn run(args: Vec<String>, extra_classpath: &[String]) -> Result<ExitCode> {
+pub async fn run(args: Vec<String>, options: LaunchOptions) -> Result<ExitCode> {
+    // Sharded runs pick and split features in the launcher
+    if let Some((sharding, args)) = take_shard_options(&args)? {
+        return run_sharded(args, sharding, &options).await;
+    }
+    Ok(exit_code(launch(args, &options).await?, &options.config))
+}
+
+/// Launch a delegated command and wait for it, without mapping its exit code.
+pub async fn launch(args: Vec<String>, options: &LaunchOptions) -> Result<RunOutcome> {
     let paths = KaratePaths::new();
-    let config = load_merged_config()?;
+    let config = load_merged_config(&options.config)?;
 
-    // Find JRE - check config override first
-    let java_executable = if let Some(jre_path) = &config.jre_path {
-        find_java_in_dir(&PathBuf::from(jre_path))?
-    } else {
-        let jre = find_active_jre()?.ok_or(KarateError::NotBootstrapped)?;
-        jre.java_executable
+    // Config problems don't block a run, but shouldn't be silently swallowed either
+    for issue in validate_config_files()? {
+        eprintln!("{} {}", style("warning:").yellow().bold(), issue);
+    }
+
+    // A dry run only prints where agents would be loaded from
+    if options.dry_run.is_none() {
+        ensure_agents(&config, &paths).await?;
+    }
+
+    let mut plan = plan_launch(&config, &paths, &args, &options.extra_classpath)?;
+    if let Some(port) = options.debug_port {
+        plan.jvm_args
+            .push(jdwp_arg(DEBUG_HOST, port, options.debug_suspend));
+    }
+    let (command_args, argfile) = plan.command_args(&paths.cache);
+
+    // A dry run has no side effects: the argfile path is printed but not written
+    if let Some(format) = options.dry_run {
+        let argfile = argfile.as_ref().map(|a| a.path.as_path());
+        print_dry_run(&plan, &command_args, argfile, format)?;
+        return Ok(RunOutcome::DryRun);
+    }
+    if let Some(argfile) = &argfile {
+        argfile.write()?;
+    }
+
+    if let Some(port) = options.debug_port {
+        // The JVM would otherwise die with a terse JDWP bind error
+        ensure_port_free(DEBUG_HOST, port)?;
+        let waiting = if options.debug_suspend {
+            " (suspended until a debugger attaches)"
+        } else {
+            ""
+        };
+        eprintln!(
+            "{} JVM debugger listening on {}:{}{}",
+            style("▶").cyan().bold(),
+            DEBUG_HOST,
+            port,
+            waiting
+        );
+    }
+
+    let started = SystemTime::now();
+    let timer = Instant::now();
+    let outcome = execute(&plan, &command_args, options).await?;
+    record_run(
+        &paths,
+        &args,
+        options,
+        &plan,
+        started,
+        timer.elapsed(),
+        outcome,
+    );
+    Ok(outcome)
+}
+
+/// Run a resolved command in a fresh JVM.
+async fn execute(
+    plan: &LaunchPlan,
+    command_args: &[String],
+    options: &LaunchOptions,
+) -> Result<RunOutcome> {
+    // Execute and wait
+    let artifacts = Artifacts::snapshot(&plan.jvm_args, plan.oom_marker.as_deref());
+    let cmd = java_command(plan, command_args);
+    let status = match run_supervised(cmd, &options.supervision).await? {
+        Outcome::Exited(status) => status,
+        Outcome::TimedOut => {
+            artifacts.take_oom_marker();
+            let limit = options.supervision.timeout.unwrap_or_default();
+            eprintln!(
+                "{} Timed out after {}; JVM stopped",
+                style("✗").red(),
+                format_duration(limit)
+            );
+            return Ok(RunOutcome::TimedOut);
+        }
     };
 
+    if status.success() {
+        artifacts.take_oom_marker();
+    } else {
+        let evidence =
+            Evidence::collect(&artifacts, &status, options.supervision.log_file.as_deref());
+        if let Some(diagnosis) = diagnose(&evidence) {
+            print_diagnosis(&diagnosis, &plan.java, &plan.jvm_args);
+        }
+    }
+
+    Ok(RunOutcome::from_status(&status))
+}
+
+/// The java command for a plan, with the environment from config (and the selected
+/// profile) and env files.
+pub fn java_command(plan: &LaunchPlan, command_args: &[String]) -> Command {
+    let mut cmd = Command::new(&plan.java);
+    cmd.args(command_args);
+    for (name, var) in &plan.env {
+        debug!("env {name}={REDACTED} (from {})", var.source);
+        cmd.env(name, &var.value);
+    }
+    cmd
+}
+
+/// The launcher exit code for how a delegated command finished, with failed or killed
+/// JVMs mapped by `exit_code_mode`.
+pub fn exit_code(outcome: RunOutcome, selection: &ConfigSelection) -> ExitCode {
+    exit_code_in_mode(outcome, exit_code_mode(selection))
+}
+
+/// The launcher exit code for how a command finished under an `exit_code_mode`.
+pub fn exit_code_in_mode(outcome: RunOutcome, mode: ExitCodeMode) -> ExitCode {
+    match outcome {
+        RunOutcome::Exited(0) | RunOutcome::DryRun => ExitCode::Success,
+        RunOutcome::TimedOut => ExitCode::Timeout,
+        RunOutcome::Exited(code) => ExitCode::Jvm(ExitCode::jvm_passthrough(code, mode)),
+        RunOutcome::Signaled(signal) => ExitCode::Jvm(ExitCode::signal_passthrough(signal, mode)),
+    }
+}
+
+/// The configured `exit_code_mode`, or the default when config can't be loaded.
+pub fn exit_code_mode(selection: &ConfigSelection) -> ExitCodeMode {
+    load_merged_config(selection)
+        .ok()
+        .and_then(|config| config.exit_code_mode)
+        .unwrap_or_default()
+}
+
+/// Resolve the java executable, Karate JAR, JVM options, classpath and arguments for
+/// a delegated command.
+pub fn plan_launch(
+    config: &Config,
+    paths: &KaratePaths,
+    args: &[String],
+    extra_classpath: &[String],
+) -> Result<LaunchPlan> {
+    let java = resolve_java(config)?;
+
     // Find Karate JAR - check config override first
     let dist_dir = config
         .dist_path
+        .as_ref()
         .map(PathBuf::from)
         .unwrap_or_else(|| paths.dist.clone());
 
-    let jar_path = find_karate_jar(&dist_dir, &config.karate_version)?;
+    let jar = find_karate_jar(&dist_dir, &config.karate_version)?;
+
+    let classpath = build_classpath(paths, &jar, &config.classpath, extra_classpath)?;
 
-    // Build classpath
-    let classpath = build_classpath(&paths, &jar_path, extra_classpath)?;
+    // Java agents go after the configured JVM options
+    let mut jvm_args = jvm_args(config, args)?;
+    jvm_args.extend(agent_args(config, paths)?);
 
-    // Build JVM command
-    let mut cmd = Command::new(&java_executable);
+    // Container-aware heap/CPU defaults, unless already set explicitly (in the
+    // inherited environment too, which config env values override)
+    let env = resolve_env(config)?;
+    let env_values: Vec<(String, String)> = std::env::vars()
+        .chain(
+            env.iter()
+                .map(|(name, var)| (name.clone(), var.value.clone())),
+        )
+        .collect();
+    let resources = JvmResources::resolve(&jvm_args, &env_values);
+    jvm_args.splice(0..0, resources.defaults);
 
-    // Add JVM opts from config
-    if let Some(jvm_opts) = &config.jvm_opts {
-        for opt in jvm_opts.split_whitespace() {
-            cmd.arg(opt);
+    // Class-data sharing archive from install time, when it matches this JRE and classpath
+    if config.cds != Some(false) {
+        if let Some(arg) = shared_archive_arg(paths, &java, &classpath, &jvm_args) {
+            jvm_args.insert(0, arg);
         }
     }
 
-    // Add classpath
-    cmd.arg("-cp").arg(&classpath);
+    // Report OutOfMemoryError through a marker file, unless jvm_opts has its own hook
+    let oom_marker = oom_marker(paths, &jvm_args);
+    if let Some(marker) = &oom_marker {
+        jvm_args.insert(0, oom_hook_arg(marker));
+    }
 
-    // Add main class
-    cmd.arg("com.intuit.karate.Main");
+    Ok(LaunchPlan {
+        java,
+        jar,
+        jvm_args,
+        classpath,
+        main_class: MAIN_CLASS.to_string(),
+        args: karate_args(config, args),
+        env,
+        oom_marker,
+    })
+}
 
-    // Add user arguments
-    cmd.args(&args);
+/// Print the resolved command for --dry-run.
+fn print_dry_run(
+    plan: &LaunchPlan,
+    command_args: &[String],
+    argfile: Option<&Path>,
+    format: OutputFormat,
+) -> Result<()> {
+    let env: BTreeMap<&str, EnvVar> = plan
+        .env
+        .iter()
+        .map(|(name, var)| {
+            let redacted = EnvVar {
+                value: REDACTED.to_string(),
+                source: var.source.clone(),
+            };
+            (name.as_str(), redacted)
+        })
+        .collect();
+    let command: Vec<String> = std::iter::once(plan.java.to_string_lossy().to_string())
+        .chain(command_args.iter().cloned())
+        .collect();
 
-    // Execute and wait
-    let status = cmd
-        .status()
-        .with_context(|| "Failed to execute Karate JAR")?;
+    if format == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct DryRun<'a> {
+            #[serde(flatten)]
+            plan: &'a LaunchPlan,
+            env: &'a BTreeMap<&'a str, EnvVar>,
+            argfile: Option<&'a Path>,
+            command: &'a [String],
+        }
+        let dry_run = DryRun {
+            plan,
+            env: &env,
+            argfile,
+            command: &command,
+        };
+        println!("{}", serde_json::to_string_pretty(&dry_run)?);
+        return Ok(());
+    }
 
-    if status.success() {
-        Ok(ExitCode::Success)
+    println!("{} Resolved command (dry run)", style("▶").cyan().bold());
+    println!();
+    println!("  Java:       {}", style(plan.java.display()).green());
+    println!("  JAR:        {}", style(plan.jar.display()).green());
+    println!("  Main class: {}", plan.main_class);
+    println!();
+
+    println!("{}", style("JVM Options").bold().underlined());
+    if plan.jvm_args.is_empty() {
+        println!("  {}", style("None").dim());
+    }
+    for arg in &plan.jvm_args {
+        println!("  {}", arg);
+    }
+    println!();
+
+    println!("{}", style("Classpath").bold().underlined());
+    for entry in &plan.classpath {
+        println!(
+            "  {} {}",
+            entry.path,
+            style(format!("({})", entry.source)).dim()
+        );
+    }
+    println!();
+
+    println!("{}", style("Arguments").bold().underlined());
+    println!("  {}", shell_join(&plan.args));
+    println!();
+
+    if !env.is_empty() {
+        println!("{}", style("Environment").bold().underlined());
+        for (name, var) in &env {
+            println!(
+                "  {}={} {}",
+                name,
+                var.value,
+                style(format!("({})", var.source)).dim()
+            );
+        }
+        println!();
+    }
+
+    if let Some(argfile) = argfile {
+        println!("{}", style("Argfile").bold().underlined());
+        println!("  {}", argfile.display());
+        println!();
+    }
+
+    println!("{}", style("Command").bold().underlined());
+    println!("  {}", shell_join(&command));
+
+    Ok(())
+}
+
+/// Join arguments into a command line that can be pasted into a POSIX shell.
+pub fn shell_join(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote an argument for a POSIX shell, leaving plain words unquoted.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// JVM options for a delegated command: global `jvm_opts`, then the `commands`
+/// entry for the first argument. Later options win in the JVM (e.g. `-Xmx`).
+fn jvm_args(config: &Config, args: &[String]) -> Result<Vec<String>> {
+    let mut jvm_args = match &config.jvm_opts {
+        Some(opts) => opts.args()?,
+        None => Vec::new(),
+    };
+    if let Some(command) = command_config(config, args) {
+        jvm_args.extend(command.jvm_args()?);
+    }
+    Ok(jvm_args)
+}
+
+/// Arguments for Karate's Main: the command name, its configured default
+/// arguments, then the rest of the user's arguments.
+fn karate_args(config: &Config, args: &[String]) -> Vec<String> {
+    match command_config(config, args) {
+        Some(command) if !command.args.is_empty() => {
+            let mut karate_args = vec![args[0].clone()];
+            karate_args.extend(command.args.iter().cloned());
+            karate_args.extend(args[1..].iter().cloned());
+            karate_args
+        }
+        _ => args.to_vec(),
+    }
+}
+
+/// The `commands` entry for a delegated command, keyed by its first argument.
+fn command_config<'a>(config: &'a Config, args: &[String]) -> Option<&'a CommandConfig> {
+    args.first().and_then(|name| config.commands.get(name))
+}
+
+/// JVM arguments passed as an `@argfile`. It is named by content hash, so identical
+/// launches reuse it and concurrent runs never clobber each other's file, and is only
+/// written when the command is actually launched.
+#[derive(Debug, Clone)]
+pub struct Argfile {
+    pub path: PathBuf,
+    content: String,
+}
+
+impl Argfile {
+    /// The argfile for JVM arguments in the cache directory.
+    fn new(cache_dir: &Path, args: &[String]) -> Self {
+        let content: String = args
+            .iter()
+            .map(|arg| quote_argfile_arg(arg) + "\n")
+            .collect();
+        let hash = hex::encode(Sha256::digest(content.as_bytes()));
+        let path = cache_dir
+            .join("argfiles")
+            .join(format!("jvm-{}.args", &hash[..16]));
+        Argfile { path, content }
+    }
+
+    /// Write the argfile, then remove old ones.
+    pub fn write(&self) -> Result<()> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        // Write and rename, so a concurrent launch (such as another shard) never reads
+        // a partial file. Rewriting also refreshes the modification time pruning goes by.
+        let partial = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&partial, &self.content)
+            .with_context(|| format!("Failed to write argfile {}", partial.display()))?;
+        if let Err(e) = std::fs::rename(&partial, &self.path) {
+            let _ = std::fs::remove_file(&partial);
+            // Windows can't replace a file that a starting JVM still has open; its
+            // content is the same
+            if !self.path.is_file() {
+                return Err(e)
+                    .with_context(|| format!("Failed to write argfile {}", self.path.display()));
+            }
+        }
+        debug!("JVM arguments written to argfile {}", self.path.display());
+
+        prune_argfiles(dir, &self.path);
+        Ok(())
+    }
+}
+
+/// Remove argfiles (and partial ones) that haven't been written for a day.
+fn prune_argfiles(dir: &Path, keep: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let old = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > ARGFILE_MAX_AGE);
+        if old && path != keep {
+            debug!("Removing old argfile {}", path.display());
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+/// Quote one argument for a java launcher argfile: always double-quoted, with
+/// backslash as the escape character (so Windows paths need `\\`).
+fn quote_argfile_arg(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The JVM argument that starts a JDWP debug agent.
+fn jdwp_arg(host: &str, port: u16, suspend: bool) -> String {
+    let suspend = if suspend { "y" } else { "n" };
+    format!("-agentlib:jdwp=transport=dt_socket,server=y,suspend={suspend},address={host}:{port}")
+}
+
+/// Fail if something is already listening on the debug port.
+fn ensure_port_free(host: &str, port: u16) -> Result<()> {
+    std::net::TcpListener::bind((host, port)).map(drop).map_err(|e| {
+        anyhow::anyhow!(
+            "Cannot start JVM debugger: port {} is not available ({}). Choose another with --debug-jvm=<port>",
+            port,
+            e
+        )
+    })
+}
+
+/// Find the java executable - `jre_path` config override first, then the active JRE.
+pub fn resolve_java(config: &Config) -> Result<PathBuf> {
+    if let Some(jre_path) = &config.jre_path {
+        find_java_in_dir(&PathBuf::from(jre_path))
     } else {
-        let code = status.code().unwrap_or(1);
-        // Pass through JVM exit code
-        std::process::exit(ExitCode::jvm_passthrough(code));
+        let jre = find_active_jre()?.ok_or(KarateError::NotBootstrapped)?;
+        Ok(jre.java_executable)
     }
 }
 
@@ -80,7 +663,11 @@ fn find_java_in_dir(jre_dir: &Path) -> Result<PathBuf> {
         }
     }
 
-    anyhow::bail!("Could not find java executable in {}", jre_dir.display())
+    Err(KarateError::Jre(format!(
+        "Could not find java executable in {}",
+        jre_dir.display()
+    ))
+    .into())
 }
 
 /// Find the Karate JAR to use.
@@ -97,11 +684,12 @@ fn find_karate_jar(dist_dir: &Path, karate_version: &str) -> Result<PathBuf> {
         if pinned.exists() {
             return Ok(pinned);
         }
-        anyhow::bail!(
-            "karate_version is pinned to {karate_version} in config, but {} does not exist.\n\
+        return Err(KarateError::JarNotFound(format!(
+            "{} (karate_version is pinned to {karate_version} in config).\n\
              Install it with: karate setup --item jar --karate-version {karate_version}",
             pinned.display()
-        );
+        ))
+        .into());
     }
 
     // Find any karate-*.jar in dist (excluding robot JARs)
@@ -126,36 +714,54 @@ fn find_karate_jar(dist_dir: &Path, karate_version: &str) -> Result<PathBuf> {
         .ok_or_else(|| KarateError::NotBootstrapped.into())
 }
 
-/// Build the classpath string.
-/// Order: karate jar → ext jars (global + local) → extra classpath (--cp flags)
+/// Build the classpath entries.
+/// Order: karate jar → ext jars (global, then local) → profile classpath → --cp flags
 fn build_classpath(
     paths: &KaratePaths,
     jar_path: &Path,
+    profile_classpath: &[String],
     extra_classpath: &[String],
-) -> Result<String> {
-    let mut classpath_parts = vec![jar_path.to_string_lossy().to_string()];
+) -> Result<Vec<ClasspathEntry>> {
+    let mut entries = vec![ClasspathEntry {
+        path: jar_path.to_string_lossy().to_string(),
+        source: ClasspathSource::Dist,
+    }];
 
     // Add extensions from both global and local ext directories
-    for ext_dir in paths.all_ext_dirs() {
+    // (all_ext_dirs lists the global one first)
+    for (index, ext_dir) in paths.all_ext_dirs().into_iter().enumerate() {
+        let source = if index == 0 {
+            ClasspathSource::GlobalExt
+        } else {
+            ClasspathSource::LocalExt
+        };
         if ext_dir.exists() {
-            for entry in std::fs::read_dir(&ext_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.extension().map(|e| e == "jar").unwrap_or(false) {
-                    classpath_parts.push(path.to_string_lossy().to_string());
-                }
-            }
+            // Sorted, so the classpath (and any argfile) is stable across runs
+            let mut jars: Vec<PathBuf> = std::fs::read_dir(&ext_dir)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().map(|e| e == "jar").unwrap_or(false))
+                .collect();
+            jars.sort();
+            entries.extend(jars.into_iter().map(|path| ClasspathEntry {
+                path: path.to_string_lossy().to_string(),
+                source,
+            }));
         }
     }
 
-    // Add extra classpath entries from --cp flags
-    for entry in extra_classpath {
-        classpath_parts.push(entry.clone());
+    // Add extra classpath entries from the profile, then --cp flags
+    for (list, source) in [
+        (profile_classpath, ClasspathSource::Profile),
+        (extra_classpath, ClasspathSource::Cli),
+    ] {
+        entries.extend(list.iter().map(|path| ClasspathEntry {
+            path: path.clone(),
+            source,
+        }));
     }
 
-    // Join with platform-appropriate separator
-    let separator = if cfg!(windows) { ";" } else { ":" };
-    Ok(classpath_parts.join(separator))
+    Ok(entries)
 }
 
 #[cfg(test)]
@@ -200,4 +806,168 @@ mod tests {
         let jar = find_karate_jar(dist.path(), "latest").unwrap();
         assert_eq!(jar.file_name().unwrap(), "karate-1.5.2.jar");
     }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn per_command_options_follow_global_ones() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "jvm_opts": "-Xmx4g",
+            "commands": {
+                "mock": {
+                    "jvm_opts": "-Xmx256m",
+                    "system_properties": { "karate.env": "mock" },
+                    "args": ["-p", "8080"]
+                }
+            }
+        }))
+        .unwrap();
+
+        let mock = args(&["mock", "-m", "api.feature"]);
+        assert_eq!(
+            jvm_args(&config, &mock).unwrap(),
+            args(&["-Xmx4g", "-Xmx256m", "-Dkarate.env=mock"])
+        );
+        assert_eq!(
+            karate_args(&config, &mock),
+            args(&["mock", "-p", "8080", "-m", "api.feature"])
+        );
+
+        let run = args(&["run", "x.feature"]);
+        assert_eq!(jvm_args(&config, &run).unwrap(), args(&["-Xmx4g"]));
+        assert_eq!(karate_args(&config, &run), run);
+    }
+
+    #[test]
+    fn argfile_args_are_quoted_for_the_java_launcher() {
+        assert_eq!(quote_argfile_arg("-Xmx1g"), r#""-Xmx1g""#);
+        assert_eq!(
+            quote_argfile_arg(r"C:\Program Files\ext\a.jar"),
+            r#""C:\\Program Files\\ext\\a.jar""#
+        );
+        assert_eq!(
+            quote_argfile_arg(r#"-Dkarate.options="--tags @smoke""#),
+            r#""-Dkarate.options=\"--tags @smoke\"""#
+        );
+        assert_eq!(
+            quote_argfile_arg("# not a comment"),
+            r##""# not a comment""##
+        );
+    }
+
+    #[test]
+    fn argfile_is_only_written_when_asked() {
+        let cache = tempfile::tempdir().unwrap();
+        let args = args(&["-cp", "/a b/x.jar", MAIN_CLASS]);
+
+        let argfile = Argfile::new(cache.path(), &args);
+        assert!(!argfile.path.exists());
+        argfile.write().unwrap();
+        argfile.write().unwrap();
+        let first = argfile.path;
+        assert_eq!(
+            std::fs::read_to_string(&first).unwrap(),
+            format!("\"-cp\"\n\"/a b/x.jar\"\n\"{MAIN_CLASS}\"\n")
+        );
+        assert_eq!(
+            std::fs::read_dir(first.parent().unwrap()).unwrap().count(),
+            1
+        );
+    }
+
+    #[test]
+    fn old_argfiles_are_pruned() {
+        let cache = tempfile::tempdir().unwrap();
+        let old = Argfile::new(cache.path(), &args(&["-Xmx1g"]));
+        old.write().unwrap();
+        let old = old.path;
+        let two_days_ago = SystemTime::now() - ARGFILE_MAX_AGE * 2;
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(two_days_ago)
+            .unwrap();
+
+        let new = Argfile::new(cache.path(), &args(&["-Xmx2g"]));
+        new.write().unwrap();
+        assert!(new.path.is_file());
+        assert!(!old.exists());
+    }
+
+    #[test]
+    fn classpath_entries_are_labeled_by_source() {
+        let home = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(home.path().join("ext")).unwrap();
+        std::fs::create_dir_all(project.path().join("ext")).unwrap();
+        std::fs::write(home.path().join("ext/b.jar"), b"jar").unwrap();
+        std::fs::write(home.path().join("ext/a.jar"), b"jar").unwrap();
+        std::fs::write(home.path().join("ext/notes.txt"), b"txt").unwrap();
+        std::fs::write(project.path().join("ext/local.jar"), b"jar").unwrap();
+
+        let paths = KaratePaths {
+            home: home.path().to_path_buf(),
+            project: None,
+            local: Some(project.path().to_path_buf()),
+            dist: home.path().join("dist"),
+            jre: home.path().join("jre"),
+            ext: home.path().join("ext"),
+            cache: home.path().join("cache"),
+            global_config: home.path().join("karate-cli.json"),
+        };
+
+        let entries = build_classpath(
+            &paths,
+            Path::new("karate.jar"),
+            &args(&["lib/profile.jar"]),
+            &args(&["cli.jar"]),
+        )
+        .unwrap();
+
+        let summary: Vec<(String, ClasspathSource)> = entries
+            .iter()
+            .map(|e| {
+                let name = Path::new(&e.path).file_name().unwrap();
+                (name.to_string_lossy().to_string(), e.source)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("karate.jar".to_string(), ClasspathSource::Dist),
+                ("a.jar".to_string(), ClasspathSource::GlobalExt),
+                ("b.jar".to_string(), ClasspathSource::GlobalExt),
+                ("local.jar".to_string(), ClasspathSource::LocalExt),
+                ("profile.jar".to_string(), ClasspathSource::Profile),
+                ("cli.jar".to_string(), ClasspathSource::Cli),
+            ]
+        );
+    }
+
+    #[test]
+    fn debug_agent_argument_and_port_check() {
+        assert_eq!(
+            jdwp_arg("127.0.0.1", 5005, false),
+            "-agentlib:jdwp=transport=dt_socket,server=y,suspend=n,address=127.0.0.1:5005"
+        );
+        assert!(jdwp_arg("127.0.0.1", 5005, true).contains("suspend=y"));
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let err = ensure_port_free("127.0.0.1", port).unwrap_err();
+        assert!(err.to_string().contains(&format!("port {port}")));
+        drop(listener);
+        assert!(ensure_port_free("127.0.0.1", port).is_ok());
+    }
+
+    #[test]
+    fn shell_quoting_for_printed_commands() {
+        assert_eq!(shell_quote("-Xmx1g"), "-Xmx1g");
+        assert_eq!(shell_quote("/a b/c.jar"), "'/a b/c.jar'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
 }
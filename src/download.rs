@@ -1,5 +1,6 @@
 //! File downloading with progress and checksum verification.
 
+use crate::error::KarateError;
 use anyhow::{Context, Result};
 use indicatif::{ProgressBar, ProgressStyle};
 use serde::Deserialize;
@@ -34,17 +35,16 @@ pub async fn fetch_latest_release(owner: &str, repo: &str) -> Result<GitHubRelea
         .user_agent("karate-cli")
         .build()?;
 
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .with_context(|| format!("Failed to fetch release info from {}", url))?;
+    let response = client.get(&url).send().await.with_context(|| {
+        KarateError::Network(format!("Failed to fetch release info from {}", url))
+    })?;
 
     if !response.status().is_success() {
-        anyhow::bail!(
+        return Err(KarateError::Network(format!(
             "Failed to fetch release: HTTP {}",
             response.status().as_u16()
-        );
+        ))
+        .into());
     }
 
     response
@@ -85,23 +85,28 @@ pub async fn resolve_justj_jre(java_version: u8, platform: &str) -> Result<JustJ
         .user_agent("karate-cli")
         .build()?;
 
-    let response = client
-        .get(&manifest_url)
-        .send()
-        .await
-        .with_context(|| format!("Failed to fetch JustJ manifest from {}", manifest_url))?;
+    let response = client.get(&manifest_url).send().await.with_context(|| {
+        KarateError::Network(format!(
+            "Failed to fetch JustJ manifest from {}",
+            manifest_url
+        ))
+    })?;
 
     if !response.status().is_success() {
-        anyhow::bail!(
+        return Err(KarateError::Network(format!(
             "Failed to fetch JustJ manifest: HTTP {}\n\n\
             This could mean Java {} is not available from JustJ.\n\
             Check available versions at: https://download.eclipse.org/justj/jres/",
             response.status().as_u16(),
             java_version
-        );
+        ))
+        .into());
     }
 
-    let manifest = response.text().await?;
+    let manifest = response
+        .text()
+        .await
+        .with_context(|| KarateError::Network("Failed to read JustJ manifest".to_string()))?;
 
     // Find the full.stripped JRE for our platform
     // Pattern: org.eclipse.justj.openjdk.hotspot.jre.full.stripped-{version}-{platform}.tar.gz
@@ -112,15 +117,13 @@ pub async fn resolve_justj_jre(java_version: u8, platform: &str) -> Result<JustJ
                 && line.contains(justj_platform)
         })
         .ok_or_else(|| {
-            anyhow::anyhow!(
+            KarateError::Jre(format!(
                 "JustJ does not provide JRE {} for platform '{}'\n\n\
                 Check supported platforms at: {}\n\n\
                 Workaround: Set 'jre_path' in config to use a manually installed JRE:\n  \
                 karate config --global",
-                java_version,
-                platform,
-                manifest_url
-            )
+                java_version, platform, manifest_url
+            ))
         })?;
 
     // Entry format: ../20251104_1502/org.eclipse.justj...tar.gz
@@ -151,14 +154,18 @@ pub async fn resolve_justj_jre(java_version: u8, platform: &str) -> Result<JustJ
 pub async fn download_file(url: &str, dest: &Path, expected_sha256: Option<&str>) -> Result<()> {
     let client = reqwest::Client::new();
 
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .with_context(|| format!("Failed to start download from {}", url))?;
+    let response =
+        client.get(url).send().await.with_context(|| {
+            KarateError::DownloadFailed(format!("could not connect to {}", url))
+        })?;
 
     if !response.status().is_success() {
-        anyhow::bail!("Download failed: HTTP {}", response.status().as_u16());
+        return Err(KarateError::DownloadFailed(format!(
+            "HTTP {} from {}",
+            response.status().as_u16(),
+            url
+        ))
+        .into());
     }
 
     let total_size = response.content_length();
@@ -199,7 +206,9 @@ pub async fn download_file(url: &str, dest: &Path, expected_sha256: Option<&str>
 
     use futures_util::StreamExt;
     while let Some(chunk) = stream.next().await {
-        let chunk = chunk.with_context(|| "Failed to read download chunk")?;
+        let chunk = chunk.with_context(|| {
+            KarateError::DownloadFailed(format!("connection lost while reading {}", url))
+        })?;
         file.write_all(&chunk).await?;
         hasher.update(&chunk);
         pb.inc(chunk.len() as u64);
@@ -216,12 +225,12 @@ pub async fn download_file(url: &str, dest: &Path, expected_sha256: Option<&str>
         if actual != expected.to_lowercase() {
             // Clean up temp file
             let _ = std::fs::remove_file(&temp_path);
-            anyhow::bail!(
-                "Checksum mismatch for {}: expected {}, got {}",
-                dest.display(),
-                expected,
-                actual
-            );
+            return Err(KarateError::ChecksumMismatch {
+                file: dest.display().to_string(),
+                expected: expected.to_string(),
+                actual,
+            }
+            .into());
         }
     }
 
@@ -238,7 +247,6 @@ pub async fn download_file(url: &str, dest: &Path, expected_sha256: Option<&str>
 }
 
 /// Calculate SHA256 of a file.
-#[allow(dead_code)]
 pub fn calculate_sha256(path: &Path) -> Result<String> {
     let content = std::fs::read(path)?;
     let hash = Sha256::digest(&content);
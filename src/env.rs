@@ -0,0 +1,188 @@
+//! Environment for delegated commands: the config `env` map and dotenv files.
+
+use crate::config::Config;
+use crate::error::KarateError;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Placeholder shown instead of environment values in diagnostics and logs.
+pub const REDACTED: &str = "***";
+
+/// An environment variable set for delegated commands.
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvVar {
+    pub value: String,
+    /// Where the value came from: "config" or the dotenv file path
+    pub source: String,
+}
+
+/// Resolve the environment for delegated commands, keyed by variable name.
+///
+/// Precedence (later wins): config `env` map → `env_files` in order (config
+/// files first, then `--env-file` flags).
+pub fn resolve_env(config: &Config) -> Result<BTreeMap<String, EnvVar>> {
+    let mut env: BTreeMap<String, EnvVar> = config
+        .env
+        .iter()
+        .map(|(name, value)| {
+            let var = EnvVar {
+                value: value.clone(),
+                source: "config".to_string(),
+            };
+            (name.clone(), var)
+        })
+        .collect();
+
+    for file in &config.env_files {
+        for (name, value) in load_env_file(Path::new(file))? {
+            let var = EnvVar {
+                value,
+                source: file.clone(),
+            };
+            env.insert(name, var);
+        }
+    }
+
+    Ok(env)
+}
+
+/// Load a dotenv file.
+fn load_env_file(path: &Path) -> Result<Vec<(String, String)>> {
+    if !path.exists() {
+        return Err(
+            KarateError::Config(format!("env file {} does not exist", path.display())).into(),
+        );
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read env file {}", path.display()))?;
+
+    parse_dotenv(&content)
+        .map_err(|e| KarateError::Config(format!("{}: {e}", path.display())).into())
+}
+
+/// Parse dotenv content: `KEY=value` lines with optional `export ` prefix,
+/// `#` comments, and single-quoted (literal) or double-quoted (escapes) values.
+fn parse_dotenv(content: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {line_no}: expected KEY=value"))?;
+
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(format!("line {line_no}: invalid variable name '{key}'"));
+        }
+
+        let value = parse_dotenv_value(value.trim_start())
+            .ok_or_else(|| format!("line {line_no}: unterminated quote"))?;
+        vars.push((key.to_string(), value));
+    }
+
+    Ok(vars)
+}
+
+/// Parse the value part of a dotenv line. Returns `None` for an unterminated quote.
+fn parse_dotenv_value(raw: &str) -> Option<String> {
+    if let Some(rest) = raw.strip_prefix('\'') {
+        return rest.find('\'').map(|end| rest[..end].to_string());
+    }
+
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Some(value),
+                '\\' => match chars.next()? {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    'r' => value.push('\r'),
+                    other => value.push(other),
+                },
+                c => value.push(c),
+            }
+        }
+        return None;
+    }
+
+    // Unquoted: an inline comment starts at " #"
+    let value = match raw.find(" #") {
+        Some(i) => &raw[..i],
+        None => raw,
+    };
+    Some(value.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dotenv_forms() {
+        let vars = parse_dotenv(
+            "# comment\n\
+             BASE_URL=https://api.example.com # inline\n\
+             export TOKEN='s3cr3t # not a comment'\n\
+             \n\
+             GREETING=\"hello\\nworld\"\n\
+             EMPTY=\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            vars,
+            vec![
+                (
+                    "BASE_URL".to_string(),
+                    "https://api.example.com".to_string()
+                ),
+                ("TOKEN".to_string(), "s3cr3t # not a comment".to_string()),
+                ("GREETING".to_string(), "hello\nworld".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn dotenv_errors_name_the_line() {
+        assert_eq!(
+            parse_dotenv("A=1\nnot a var\n").unwrap_err(),
+            "line 2: expected KEY=value"
+        );
+        assert_eq!(
+            parse_dotenv("A=\"open\n").unwrap_err(),
+            "line 1: unterminated quote"
+        );
+    }
+
+    #[test]
+    fn env_files_override_config_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(".env");
+        std::fs::write(&file, "TOKEN=from-file\n").unwrap();
+
+        let mut config = Config::default();
+        config.env.insert("TOKEN".into(), "from-config".into());
+        config
+            .env
+            .insert("BASE_URL".into(), "http://localhost".into());
+        config.env_files.push(file.to_string_lossy().to_string());
+
+        let env = resolve_env(&config).unwrap();
+        assert_eq!(env["TOKEN"].value, "from-file");
+        assert_eq!(env["TOKEN"].source, file.to_string_lossy());
+        assert_eq!(env["BASE_URL"].source, "config");
+    }
+}
@@ -1,45 +1,65 @@
 //! Error types and exit codes for the Karate CLI.
 
+use crate::cli::OutputFormat;
+use crate::config::ExitCodeMode;
+use console::style;
+use serde::Serialize;
 use thiserror::Error;
 
 /// Exit codes as defined in the spec.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[repr(i32)]
 pub enum ExitCode {
     /// Success
-    Success = 0,
+    Success,
     /// General error
-    GeneralError = 1,
+    GeneralError,
     /// Configuration/setup error (not bootstrapped, invalid config)
-    ConfigError = 2,
+    ConfigError,
     /// Network error (download failed, manifest unreachable)
-    NetworkError = 3,
+    NetworkError,
     /// JRE error (missing, corrupt, launch failed)
-    JreError = 4,
+    JreError,
+    /// Delegated command stopped by --timeout
+    Timeout,
+    /// Passed through from a JVM, already mapped by `jvm_passthrough` or
+    /// `signal_passthrough`
+    Jvm(i32),
 }
 
 impl From<ExitCode> for i32 {
     fn from(code: ExitCode) -> Self {
-        code as i32
+        match code {
+            ExitCode::Success => 0,
+            ExitCode::GeneralError => 1,
+            ExitCode::ConfigError => 2,
+            ExitCode::NetworkError => 3,
+            ExitCode::JreError => 4,
+            ExitCode::Timeout => 5,
+            ExitCode::Jvm(code) => code,
+        }
     }
 }
 
 impl ExitCode {
-    /// Create an exit code from a JVM process exit code.
-    /// JVM exit codes are passed through as 100 + code.
-    #[allow(dead_code)]
-    pub fn from_jvm(_code: i32) -> Self {
-        // We represent JVM codes as GeneralError but the actual
-        // process exit will use the raw code
-        ExitCode::GeneralError
+    /// Get the raw exit code for JVM pass-through under an `exit_code_mode`.
+    pub fn jvm_passthrough(code: i32, mode: ExitCodeMode) -> i32 {
+        if code == 0 {
+            return 0;
+        }
+        match mode {
+            ExitCodeMode::Offset => 100 + code.abs().min(155), // Cap at 255
+            // Exit statuses are truncated to a byte, so 256 must not become 0
+            ExitCodeMode::Raw => code.clamp(1, 255),
+            ExitCodeMode::Binary => 1,
+        }
     }
 
-    /// Get the raw exit code for JVM pass-through.
-    pub fn jvm_passthrough(code: i32) -> i32 {
-        if code == 0 {
-            0
-        } else {
-            100 + code.abs().min(155) // Cap at 255
+    /// Get the raw exit code for a JVM killed by a signal: 128 + signal, as shells
+    /// report it.
+    pub fn signal_passthrough(signal: i32, mode: ExitCodeMode) -> i32 {
+        match mode {
+            ExitCodeMode::Binary => 1,
+            ExitCodeMode::Offset | ExitCodeMode::Raw => 128 + signal,
         }
     }
 }
@@ -48,7 +68,7 @@ impl ExitCode {
 #[derive(Error, Debug)]
 #[allow(dead_code)]
 pub enum KarateError {
-    #[error("Karate is not set up. Run 'karate setup' first.")]
+    #[error("Karate is not set up")]
     NotBootstrapped,
 
     #[error("Configuration error: {0}")]
@@ -88,13 +108,155 @@ pub enum KarateError {
 
 impl KarateError {
     /// Get the appropriate exit code for this error.
-    #[allow(dead_code)]
     pub fn exit_code(&self) -> ExitCode {
         match self {
-            KarateError::NotBootstrapped | KarateError::Config(_) => ExitCode::ConfigError,
-            KarateError::Network(_) | KarateError::DownloadFailed(_) => ExitCode::NetworkError,
+            KarateError::NotBootstrapped | KarateError::Config(_) | KarateError::JarNotFound(_) => {
+                ExitCode::ConfigError
+            }
+            KarateError::Network(_)
+            | KarateError::DownloadFailed(_)
+            | KarateError::ChecksumMismatch { .. } => ExitCode::NetworkError,
             KarateError::Jre(_) => ExitCode::JreError,
             _ => ExitCode::GeneralError,
         }
     }
+
+    /// A stable name for this error, used in `--error-format json`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            KarateError::NotBootstrapped => "not_bootstrapped",
+            KarateError::Config(_) => "config",
+            KarateError::Network(_) => "network",
+            KarateError::Jre(_) => "jre",
+            KarateError::JarNotFound(_) => "jar_not_found",
+            KarateError::PluginNotFound(_) => "plugin_not_found",
+            KarateError::DownloadFailed(_) => "download_failed",
+            KarateError::ChecksumMismatch { .. } => "checksum_mismatch",
+            KarateError::UnsupportedPlatform { .. } => "unsupported_platform",
+            KarateError::Io(_) => "io",
+            KarateError::Other(_) => "other",
+        }
+    }
+
+    /// What the user can do about this error.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            KarateError::NotBootstrapped => Some("Run 'karate setup' first"),
+            KarateError::Config(_) => Some("Run 'karate doctor' to check your config files"),
+            KarateError::Network(_) | KarateError::DownloadFailed(_) => {
+                Some("Check your network connection and proxy settings, or try again later")
+            }
+            KarateError::Jre(_) => {
+                Some("Run 'karate jre doctor', or set jre_path with 'karate config'")
+            }
+            KarateError::JarNotFound(_) => Some("Install it with 'karate setup --item jar'"),
+            KarateError::PluginNotFound(_) => {
+                Some("Run 'karate ext list' to see installed plugins")
+            }
+            KarateError::ChecksumMismatch { .. } => {
+                Some("Download the file again; if it keeps failing, it may have been tampered with")
+            }
+            KarateError::UnsupportedPlatform { .. } => {
+                Some("Set jre_path and dist_path in config to use a manually installed JRE and JAR")
+            }
+            KarateError::Io(_) | KarateError::Other(_) => None,
+        }
+    }
+}
+
+/// A command error as reported to the user, with the exit code it ends the launcher with.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    #[serde(skip)]
+    pub exit_code: ExitCode,
+    pub code: i32,
+    pub kind: &'static str,
+    pub message: String,
+    pub hint: Option<&'static str>,
+}
+
+impl ErrorReport {
+    /// Classify an error by the first `KarateError` it carries, either as the error
+    /// itself or as context; anything else is a general error.
+    pub fn new(err: &anyhow::Error) -> Self {
+        let typed = err
+            .downcast_ref::<KarateError>()
+            .or_else(|| err.chain().find_map(|e| e.downcast_ref::<KarateError>()));
+        let (exit_code, kind, hint) = match typed {
+            Some(typed) => (typed.exit_code(), typed.kind(), typed.hint()),
+            None => (ExitCode::GeneralError, "general", None),
+        };
+        Self {
+            exit_code,
+            code: exit_code.into(),
+            kind,
+            message: format!("{err:#}"),
+            hint,
+        }
+    }
+
+    /// Print to stderr as text or as a single JSON object.
+    pub fn print(&self, format: OutputFormat) {
+        match format {
+            OutputFormat::Text => {
+                eprintln!("Error: {}", self.message);
+                if let Some(hint) = self.hint {
+                    eprintln!("{}", style(format!("Hint: {hint}")).dim());
+                }
+            }
+            OutputFormat::Json => match serde_json::to_string(self) {
+                Ok(json) => eprintln!("{json}"),
+                Err(_) => eprintln!("Error: {}", self.message),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn maps_jvm_exit_codes_by_mode() {
+        assert_eq!(ExitCode::jvm_passthrough(0, ExitCodeMode::Offset), 0);
+        assert_eq!(ExitCode::jvm_passthrough(1, ExitCodeMode::Offset), 101);
+        assert_eq!(ExitCode::jvm_passthrough(200, ExitCodeMode::Offset), 255);
+        assert_eq!(ExitCode::jvm_passthrough(3, ExitCodeMode::Raw), 3);
+        assert_eq!(ExitCode::jvm_passthrough(256, ExitCodeMode::Raw), 255);
+        assert_eq!(ExitCode::jvm_passthrough(-1, ExitCodeMode::Raw), 1);
+        assert_eq!(ExitCode::jvm_passthrough(0, ExitCodeMode::Binary), 0);
+        assert_eq!(ExitCode::jvm_passthrough(42, ExitCodeMode::Binary), 1);
+    }
+
+    #[test]
+    fn reports_typed_errors_through_context() {
+        let err = anyhow::Error::from(KarateError::Network("HTTP 503".to_string()))
+            .context("Failed to update");
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.exit_code, ExitCode::NetworkError);
+        assert_eq!(report.kind, "network");
+        assert_eq!(report.message, "Failed to update: Network error: HTTP 503");
+        assert!(report.hint.is_some());
+
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        let err = Err::<(), _>(io)
+            .context(KarateError::Config("Failed to read config".to_string()))
+            .unwrap_err();
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.code, 2);
+        assert_eq!(report.kind, "config");
+
+        let report = ErrorReport::new(&anyhow::anyhow!("boom"));
+        assert_eq!(report.exit_code, ExitCode::GeneralError);
+        assert_eq!(report.kind, "general");
+        assert_eq!(report.hint, None);
+    }
+
+    #[test]
+    fn maps_signals_like_a_shell() {
+        assert_eq!(ExitCode::signal_passthrough(9, ExitCodeMode::Offset), 137);
+        assert_eq!(ExitCode::signal_passthrough(15, ExitCodeMode::Raw), 143);
+        assert_eq!(ExitCode::signal_passthrough(6, ExitCodeMode::Binary), 1);
+    }
 }
@@ -0,0 +1,345 @@
+//! Run history - one JSON line per delegated command in `cache/history.jsonl`.
+
+use crate::delegate::{LaunchOptions, LaunchPlan, RunOutcome};
+use crate::jre::java_home;
+use crate::platform::KaratePaths;
+use crate::process::{format_duration, parse_duration};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// Entries kept in the history file; older ones are dropped.
+const MAX_ENTRIES: usize = 1000;
+
+/// A recorded delegated command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// Start time, seconds since the Unix epoch
+    pub timestamp: u64,
+    pub cwd: String,
+    /// Arguments after `karate`, e.g. `["run", "features"]`
+    pub args: Vec<String>,
+    /// Launcher options the command ran with
+    #[serde(default)]
+    pub options: RecordedOptions,
+    pub karate_version: Option<String>,
+    /// JRE home the command ran on
+    pub jre: Option<String>,
+    pub duration_ms: u64,
+    /// JVM exit code; `None` when stopped by --timeout or killed by a signal
+    pub exit_code: Option<i32>,
+    /// Signal that killed the JVM
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+    pub timed_out: bool,
+}
+
+/// The launcher options of a recorded command (--profile, --env-file, --cp,
+/// --timeout, --log-file), kept so `history rerun` launches it the same way.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub classpath: Vec<String>,
+    /// As written on the command line, e.g. `30m`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<String>,
+}
+
+impl RecordedOptions {
+    pub fn from_launch(options: &LaunchOptions) -> Self {
+        Self {
+            profile: options.config.profile.clone(),
+            env_files: options.config.env_files.clone(),
+            classpath: options.extra_classpath.clone(),
+            timeout: options.supervision.timeout.map(format_duration),
+            log_file: options
+                .supervision
+                .log_file
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+        }
+    }
+
+    /// Fill in launch options from the recording. Options given to `history rerun`
+    /// itself take precedence.
+    pub fn restore(&self, options: &mut LaunchOptions) -> Result<()> {
+        if options.config.profile.is_none() {
+            options.config.profile = self.profile.clone();
+        }
+        if options.config.env_files.is_empty() {
+            options.config.env_files = self.env_files.clone();
+        }
+        if options.extra_classpath.is_empty() {
+            options.extra_classpath = self.classpath.clone();
+        }
+        if options.supervision.timeout.is_none() {
+            if let Some(timeout) = &self.timeout {
+                options.supervision.timeout =
+                    Some(parse_duration(timeout).map_err(anyhow::Error::msg)?);
+            }
+        }
+        if options.supervision.log_file.is_none() {
+            options.supervision.log_file = self.log_file.as_ref().map(PathBuf::from);
+        }
+        Ok(())
+    }
+
+    /// The options as command-line flags, for display.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut flag = |name: &str, value: &str| {
+            args.push(name.to_string());
+            args.push(value.to_string());
+        };
+        if let Some(profile) = &self.profile {
+            flag("--profile", profile);
+        }
+        for file in &self.env_files {
+            flag("--env-file", file);
+        }
+        for entry in &self.classpath {
+            flag("--cp", entry);
+        }
+        if let Some(timeout) = &self.timeout {
+            flag("--timeout", timeout);
+        }
+        if let Some(log_file) = &self.log_file {
+            flag("--log-file", log_file);
+        }
+        args
+    }
+}
+
+impl HistoryEntry {
+    pub fn failed(&self) -> bool {
+        self.timed_out || self.exit_code != Some(0)
+    }
+
+    /// Launcher options followed by the arguments, as typed after `karate`.
+    pub fn command_line(&self) -> Vec<String> {
+        let mut line = self.options.to_args();
+        line.extend(self.args.iter().cloned());
+        line
+    }
+}
+
+pub fn history_path(paths: &KaratePaths) -> PathBuf {
+    paths.cache.join("history.jsonl")
+}
+
+/// All recorded entries, oldest first. Unreadable lines are skipped.
+pub fn load_history(paths: &KaratePaths) -> Vec<HistoryEntry> {
+    std::fs::read_to_string(history_path(paths))
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Record a finished command. History is best-effort: failures are only logged, so
+/// they never change a run's result.
+pub fn record_run(
+    paths: &KaratePaths,
+    args: &[String],
+    options: &LaunchOptions,
+    plan: &LaunchPlan,
+    started: SystemTime,
+    duration: Duration,
+    outcome: RunOutcome,
+) {
+    let (exit_code, signal, timed_out) = match outcome {
+        RunOutcome::Exited(code) => (Some(code), None, false),
+        RunOutcome::Signaled(signal) => (None, Some(signal), false),
+        RunOutcome::TimedOut => (None, None, true),
+        RunOutcome::DryRun => return,
+    };
+    let entry = HistoryEntry {
+        id: 0,
+        timestamp: started
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        cwd: std::env::current_dir()
+            .map(|d| d.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        args: args.to_vec(),
+        options: RecordedOptions::from_launch(options),
+        karate_version: plan
+            .jar
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix("karate-"))
+            .map(String::from),
+        jre: java_home(&plan.java),
+        duration_ms: duration.as_millis() as u64,
+        exit_code,
+        signal,
+        timed_out,
+    };
+    if let Err(e) = append(paths, entry) {
+        debug!("Failed to record run history: {e}");
+    }
+}
+
+/// Append an entry with the next id, trimming the file when it gets too long.
+fn append(paths: &KaratePaths, mut entry: HistoryEntry) -> Result<()> {
+    std::fs::create_dir_all(&paths.cache)?;
+
+    // Parallel launches (shards, CI jobs sharing a home) take turns, so no two entries
+    // get the same id. The lock is released when the file is dropped.
+    let lock = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(paths.cache.join("history.lock"))?;
+    lock.lock()?;
+
+    let mut entries = load_history(paths);
+    entry.id = entries.last().map(|e| e.id + 1).unwrap_or(1);
+
+    if entries.len() < MAX_ENTRIES {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(history_path(paths))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        return Ok(());
+    }
+
+    entries.push(entry);
+    let keep = &entries[entries.len() - MAX_ENTRIES..];
+    let mut content = String::new();
+    for entry in keep {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    // Readers never see a half-written file
+    let path = history_path(paths);
+    let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&partial, content)?;
+    std::fs::rename(&partial, &path)?;
+    Ok(())
+}
+
+/// How long ago a timestamp was, e.g. `5m ago`.
+pub fn time_ago(timestamp: u64, now: u64) -> String {
+    let secs = now.saturating_sub(timestamp);
+    match secs {
+        0..60 => format!("{secs}s ago"),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(exit_code: Option<i32>) -> HistoryEntry {
+        HistoryEntry {
+            id: 0,
+            timestamp: 1_700_000_000,
+            cwd: "/project".to_string(),
+            args: vec!["run".to_string(), "features".to_string()],
+            options: RecordedOptions::default(),
+            karate_version: Some("1.5.2".to_string()),
+            jre: None,
+            duration_ms: 1500,
+            exit_code,
+            signal: None,
+            timed_out: exit_code.is_none(),
+        }
+    }
+
+    #[test]
+    fn appends_with_increasing_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = KaratePaths::in_home(dir.path());
+        append(&paths, entry(Some(0))).unwrap();
+        append(&paths, entry(Some(1))).unwrap();
+        append(&paths, entry(None)).unwrap();
+
+        let history = load_history(&paths);
+        let ids: Vec<u64> = history.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        let failed: Vec<bool> = history.iter().map(HistoryEntry::failed).collect();
+        assert_eq!(failed, vec![false, true, true]);
+    }
+
+    #[test]
+    fn parallel_appends_get_unique_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = KaratePaths::in_home(dir.path());
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| append(&paths, entry(Some(0))).unwrap());
+            }
+        });
+
+        let mut ids: Vec<u64> = load_history(&paths).iter().map(|e| e.id).collect();
+        ids.sort();
+        assert_eq!(ids, (1..=8).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn launch_options_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = KaratePaths::in_home(dir.path());
+        let launched = LaunchOptions {
+            extra_classpath: vec!["lib/extra.jar".to_string()],
+            supervision: crate::process::Supervision {
+                timeout: Some(Duration::from_secs(1800)),
+                log_file: Some(PathBuf::from("run.log")),
+                output_prefix: None,
+            },
+            config: crate::config::ConfigSelection {
+                profile: Some("ci".to_string()),
+                env_files: vec!["ci.env".to_string()],
+            },
+            ..Default::default()
+        };
+        let mut recorded = entry(Some(0));
+        recorded.options = RecordedOptions::from_launch(&launched);
+        append(&paths, recorded).unwrap();
+
+        let loaded = &load_history(&paths)[0];
+        assert_eq!(
+            loaded.options.to_args()[..4],
+            ["--profile", "ci", "--env-file", "ci.env"]
+        );
+        let mut rerun = LaunchOptions::default();
+        loaded.options.restore(&mut rerun).unwrap();
+        assert_eq!(rerun.config.profile.as_deref(), Some("ci"));
+        assert_eq!(rerun.config.env_files, ["ci.env"]);
+        assert_eq!(rerun.extra_classpath, ["lib/extra.jar"]);
+        assert_eq!(rerun.supervision.timeout, Some(Duration::from_secs(1800)));
+        assert_eq!(rerun.supervision.log_file, Some(PathBuf::from("run.log")));
+
+        // Options given to the rerun itself win
+        let mut rerun = LaunchOptions::default();
+        rerun.config.profile = Some("perf".to_string());
+        loaded.options.restore(&mut rerun).unwrap();
+        assert_eq!(rerun.config.profile.as_deref(), Some("perf"));
+    }
+
+    #[test]
+    fn formats_time_ago() {
+        assert_eq!(time_ago(100, 130), "30s ago");
+        assert_eq!(time_ago(0, 7200), "2h ago");
+        assert_eq!(time_ago(0, 3 * 86400), "3d ago");
+    }
+}
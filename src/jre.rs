@@ -1,5 +1,6 @@
 //! JRE management.
 
+use crate::error::KarateError;
 use crate::platform::{KaratePaths, Os, Platform};
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
@@ -52,7 +53,7 @@ impl InstalledJre {
         let output = Command::new(&self.java_executable)
             .arg("-version")
             .output()
-            .with_context(|| "Failed to run java -version")?;
+            .with_context(|| KarateError::Jre("Failed to run java -version".to_string()))?;
 
         // Java prints version to stderr
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -136,6 +137,13 @@ fn check_java_home(java_home: &Path, platform: &Platform) -> Option<InstalledJre
     })
 }
 
+/// JAVA_HOME for a java executable (the directory above bin/).
+pub fn java_home(java: &Path) -> Option<String> {
+    java.parent()
+        .and_then(|bin| bin.parent())
+        .map(|home| home.to_string_lossy().to_string())
+}
+
 /// Check if java is available on PATH.
 fn check_java_on_path(platform: &Platform) -> Option<InstalledJre> {
     let java_name = platform.os.java_executable();
@@ -192,7 +200,7 @@ fn find_executable_on_path(name: &str) -> Option<PathBuf> {
 
 /// Parse Java version from java -version output.
 /// Returns (full version string, major version number).
-fn parse_java_version(java_executable: &PathBuf) -> Option<(String, u8)> {
+pub fn parse_java_version(java_executable: &Path) -> Option<(String, u8)> {
     let output = Command::new(java_executable)
         .arg("-version")
         .output()
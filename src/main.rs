@@ -1,18 +1,27 @@
+mod agents;
+mod cds;
 mod cli;
 mod commands;
 mod config;
+mod crash;
 mod delegate;
 mod download;
+mod env;
 mod error;
+mod history;
 mod jre;
 mod manifest;
 mod platform;
+mod process;
+mod reports;
+mod resources;
+mod shard;
 
 use clap::Parser;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 use crate::cli::{Cli, Command};
-use crate::error::ExitCode;
+use crate::error::{ErrorReport, ExitCode};
 
 #[tokio::main]
 async fn main() {
@@ -36,25 +45,55 @@ async fn run() -> ExitCode {
         console::set_colors_enabled(false);
     }
 
+    // Profile and env-file selection apply to every command that loads config
+    let selection = config::ConfigSelection {
+        profile: cli.profile,
+        env_files: cli.env_files,
+    };
+
+    // Launcher options for JAR-delegated commands
+    let options = delegate::LaunchOptions {
+        extra_classpath: cli.extra_classpath,
+        dry_run: cli.dry_run,
+        debug_port: cli.debug_jvm,
+        debug_suspend: cli.debug_suspend,
+        supervision: process::Supervision {
+            timeout: cli.timeout,
+            log_file: cli.log_file,
+            output_prefix: None,
+        },
+        config: selection.clone(),
+    };
+
+    let error_format = cli.error_format;
     let result = match cli.command {
         // Rust-native commands
-        Command::Setup(args) => commands::setup::run(args).await,
-        Command::Update(args) => commands::update::run(args).await,
-        Command::Config(args) => commands::config::run(args).await,
-        Command::Jre(args) => commands::jre::run(args).await,
+        Command::Setup(args) => commands::setup::run(args, &selection).await,
+        Command::Update(args) => commands::update::run(args, &selection).await,
+        Command::Config(args) => commands::config::run(args, &selection).await,
+        Command::Jre(args) => commands::jre::run(args, &options).await,
+        Command::Java(args) => commands::jre::exec("java", &args.args, &options).await,
         Command::Ext(args) => commands::plugin::run(args).await,
-        Command::Doctor(args) => commands::doctor::run(args).await,
+        Command::Doctor(args) => commands::doctor::run(args, &selection).await,
         Command::Version(args) => commands::version::run(args).await,
+        Command::Classpath(args) => commands::classpath::run(args, &options).await,
+        Command::Env(args) => commands::env::run(args, &options).await,
+        Command::Shard(args) => commands::shard::run(args).await,
 
         // JAR-delegated commands
-        Command::External(args) => delegate::run(args, &cli.extra_classpath).await,
+        Command::RerunFailed(args) => commands::rerun_failed::run(args, options).await,
+        Command::Watch(args) => commands::watch::run(args, options).await,
+        Command::History(args) => commands::history::run(args, options).await,
+        Command::External(args) => delegate::run(args, options).await,
     };
 
     match result {
         Ok(code) => code,
         Err(e) => {
-            eprintln!("Error: {e:#}");
-            ExitCode::GeneralError
+            // Typed errors choose the spec exit code; anything else is a general error
+            let report = ErrorReport::new(&e);
+            report.print(error_format);
+            report.exit_code
         }
     }
 }
@@ -1,5 +1,6 @@
 //! Manifest parsing and management for karate.sh releases.json
 
+use crate::error::KarateError;
 use crate::platform::Platform;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -131,23 +132,24 @@ pub async fn fetch_manifest_from_url(url: &str) -> Result<ReleasesManifest> {
         .user_agent("karate-cli")
         .build()?;
 
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .with_context(|| format!("Failed to fetch manifest from {}", url))?;
+    let response =
+        client.get(url).send().await.with_context(|| {
+            KarateError::Network(format!("Failed to fetch manifest from {}", url))
+        })?;
 
     if !response.status().is_success() {
-        anyhow::bail!(
-            "Failed to fetch manifest: HTTP {}",
+        return Err(KarateError::Network(format!(
+            "Failed to fetch manifest from {}: HTTP {}",
+            url,
             response.status().as_u16()
-        );
+        ))
+        .into());
     }
 
     response
         .json()
         .await
-        .with_context(|| "Failed to parse manifest JSON")
+        .with_context(|| KarateError::Network(format!("Failed to parse manifest from {}", url)))
 }
 
 /// Build a Karate JAR URL from template and version (fallback when manifest unavailable).
@@ -1,7 +1,7 @@
 //! Platform detection and OS-specific utilities.
 
 use crate::error::KarateError;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Detected platform information.
 #[derive(Debug, Clone)]
@@ -119,15 +119,97 @@ impl Os {
     }
 }
 
+/// How the project root was selected (for diagnostics).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectSource {
+    /// Explicitly set via the KARATE_PROJECT_DIR env var
+    Env,
+    /// Found by walking up from the current directory
+    Discovered,
+}
+
+impl std::fmt::Display for ProjectSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectSource::Env => write!(f, "KARATE_PROJECT_DIR"),
+            ProjectSource::Discovered => write!(f, "discovered"),
+        }
+    }
+}
+
+/// The project root: the directory that contains the project's `.karate/` folder.
+#[derive(Debug, Clone)]
+pub struct ProjectRoot {
+    pub dir: PathBuf,
+    pub source: ProjectSource,
+}
+
+impl ProjectRoot {
+    /// Resolve the project root.
+    /// Priority: KARATE_PROJECT_DIR env var → nearest ancestor of cwd with a `.karate/` dir
+    pub fn resolve() -> Option<Self> {
+        let cwd = std::env::current_dir().ok()?;
+
+        if let Ok(dir) = std::env::var("KARATE_PROJECT_DIR") {
+            if !dir.is_empty() {
+                return Some(ProjectRoot {
+                    dir: cwd.join(dir),
+                    source: ProjectSource::Env,
+                });
+            }
+        }
+
+        let global_home = KaratePaths::resolve_global_home();
+        discover_project_dir(&cwd, dirs::home_dir().as_deref(), &global_home).map(|dir| {
+            ProjectRoot {
+                dir,
+                source: ProjectSource::Discovered,
+            }
+        })
+    }
+
+    /// The project's `.karate` directory (may not exist).
+    pub fn karate_dir(&self) -> PathBuf {
+        self.dir.join(".karate")
+    }
+}
+
+/// Walk up from `start` looking for a directory containing `.karate/`, git-style.
+///
+/// The walk stops at the filesystem root, at the user's home directory (whose
+/// `.karate` is the global home, not a project), and after the first directory
+/// containing `.git` (the repository boundary). A `.karate` that is the global
+/// home (e.g. via KARATE_HOME) is never treated as a project.
+fn discover_project_dir(start: &Path, home: Option<&Path>, global_home: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        if home == Some(dir) {
+            return None;
+        }
+
+        let candidate = dir.join(".karate");
+        if candidate.is_dir() && candidate != global_home {
+            return Some(dir.to_path_buf());
+        }
+
+        if dir.join(".git").exists() {
+            return None;
+        }
+    }
+    None
+}
+
 /// Get paths to various Karate directories.
 ///
-/// Uses a two-level resolution: local `.karate/` in cwd can override
+/// Uses a two-level resolution: the project `.karate/` (found by walking up
+/// from cwd, see [`ProjectRoot::resolve`]) can override
 /// specific directories (dist, jre, ext), falling back to global home
 /// for anything not present locally.
 pub struct KaratePaths {
     /// The global home directory (KARATE_HOME or ~/.karate)
     pub home: PathBuf,
-    /// Local override directory (.karate in cwd), if it exists
+    /// Project root containing the local `.karate/`, if one was found
+    pub project: Option<ProjectRoot>,
+    /// Local override directory (project `.karate/`), if it exists
     pub local: Option<PathBuf>,
     /// Resolved dist directory (local override or global)
     pub dist: PathBuf,
@@ -143,7 +225,7 @@ pub struct KaratePaths {
 
 impl KaratePaths {
     /// Create paths with two-level resolution:
-    /// 1. Check `.karate/` in current directory for local overrides
+    /// 1. Check the project `.karate/` (cwd or nearest ancestor) for local overrides
     /// 2. Fall back to global home (`KARATE_HOME` env var or `~/.karate`)
     ///
     /// For each resource (dist, jre, ext):
@@ -151,7 +233,8 @@ impl KaratePaths {
     /// - Otherwise use global `{home}/{resource}/`
     pub fn new() -> Self {
         let home = Self::resolve_global_home();
-        let local = Self::resolve_local();
+        let project = ProjectRoot::resolve();
+        let local = Self::resolve_local(project.as_ref());
 
         // Resolve each path with local override fallback to global
         let dist = Self::resolve_path(&local, &home, "dist");
@@ -164,6 +247,7 @@ impl KaratePaths {
 
         KaratePaths {
             home,
+            project,
             local,
             dist,
             jre,
@@ -173,6 +257,22 @@ impl KaratePaths {
         }
     }
 
+    /// Paths rooted at a home directory with no project, for tests that must not
+    /// touch the real `~/.karate`.
+    #[cfg(test)]
+    pub fn in_home(home: &Path) -> Self {
+        KaratePaths {
+            home: home.to_path_buf(),
+            project: None,
+            local: None,
+            dist: home.join("dist"),
+            jre: home.join("jre"),
+            ext: home.join("ext"),
+            cache: home.join("cache"),
+            global_config: home.join("karate-cli.json"),
+        }
+    }
+
     /// Resolve the global Karate home directory.
     /// Priority: KARATE_HOME env var → ~/.karate
     fn resolve_global_home() -> PathBuf {
@@ -185,9 +285,9 @@ impl KaratePaths {
             .join(".karate")
     }
 
-    /// Check for local .karate directory in current working directory.
-    fn resolve_local() -> Option<PathBuf> {
-        let local = std::env::current_dir().ok()?.join(".karate");
+    /// Check for a local .karate directory in the project root.
+    fn resolve_local(project: Option<&ProjectRoot>) -> Option<PathBuf> {
+        let local = project?.karate_dir();
 
         if local.exists() && local.is_dir() {
             Some(local)
@@ -208,12 +308,17 @@ impl KaratePaths {
         global.join(subdir)
     }
 
-    /// Get the project-local config path (.karate/karate-cli.json in cwd).
+    /// Get the project directory: the project root, or cwd when no project root is found.
+    pub fn project_dir() -> PathBuf {
+        ProjectRoot::resolve().map(|p| p.dir).unwrap_or_else(|| {
+            std::env::current_dir().expect("Could not determine current directory")
+        })
+    }
+
+    /// Get the project-local config path (.karate/karate-cli.json in the project root).
+    /// Falls back to cwd when no project root is found, so `config --local` can create one.
     pub fn local_config() -> PathBuf {
-        std::env::current_dir()
-            .expect("Could not determine current directory")
-            .join(".karate")
-            .join("karate-cli.json")
+        Self::project_dir().join(".karate").join("karate-cli.json")
     }
 
     /// Ensure all directories exist (creates in resolved locations).
@@ -250,3 +355,63 @@ impl Default for KaratePaths {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovers_karate_dir_in_ancestor() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join(".karate")).unwrap();
+        let nested = root.path().join("features/api");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let found = discover_project_dir(&nested, None, Path::new("/nonexistent"));
+        assert_eq!(found.as_deref(), Some(root.path()));
+    }
+
+    #[test]
+    fn stops_at_git_boundary() {
+        let outer = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(outer.path().join(".karate")).unwrap();
+        let repo = outer.path().join("repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        let nested = repo.join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(
+            discover_project_dir(&nested, None, Path::new("/nonexistent")),
+            None
+        );
+    }
+
+    #[test]
+    fn git_root_itself_is_checked() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(repo.path().join(".git")).unwrap();
+        std::fs::create_dir_all(repo.path().join(".karate")).unwrap();
+        let nested = repo.path().join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let found = discover_project_dir(&nested, None, Path::new("/nonexistent"));
+        assert_eq!(found.as_deref(), Some(repo.path()));
+    }
+
+    #[test]
+    fn stops_at_home_and_ignores_global_home() {
+        let home = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(home.path().join(".karate")).unwrap();
+        let nested = home.path().join("work/project");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(
+            discover_project_dir(&nested, Some(home.path()), Path::new("/nonexistent")),
+            None
+        );
+        assert_eq!(
+            discover_project_dir(&nested, None, &home.path().join(".karate")),
+            None
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Child process supervision - timeouts and output capture for delegated JVMs.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+
+/// How long a timed-out JVM gets to shut down after SIGTERM before it's killed.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// How a child process is supervised.
+#[derive(Debug, Clone, Default)]
+pub struct Supervision {
+    /// Stop the process after this long (--timeout)
+    pub timeout: Option<Duration>,
+    /// Copy stdout and stderr to this file as well as the terminal (--log-file)
+    pub log_file: Option<PathBuf>,
+    /// Start every terminal output line with this (sharded runs)
+    pub output_prefix: Option<String>,
+}
+
+/// How a supervised process ended.
+#[derive(Debug)]
+pub enum Outcome {
+    Exited(ExitStatus),
+    TimedOut,
+}
+
+/// Spawn `cmd` and wait for it, applying the timeout, log file and output prefix.
+pub async fn run_supervised(mut cmd: Command, supervision: &Supervision) -> Result<Outcome> {
+    let log = match &supervision.log_file {
+        Some(path) => {
+            let file = tokio::fs::File::create(path)
+                .await
+                .with_context(|| format!("Failed to create log file {}", path.display()))?;
+            Some(Arc::new(Mutex::new(file)))
+        }
+        None => None,
+    };
+    let capture = log.is_some() || supervision.output_prefix.is_some();
+    if capture {
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+
+    let mut child = cmd.spawn().with_context(|| {
+        let program = Path::new(cmd.as_std().get_program());
+        format!("Failed to execute {}", program.display())
+    })?;
+
+    // Stream both outputs to the terminal and the shared log file
+    let mut tees = Vec::new();
+    if capture {
+        let prefix = supervision.output_prefix.clone();
+        if let Some(stdout) = child.stdout.take() {
+            let output = TeeOutput::new(log.clone(), prefix.clone());
+            tees.push(tokio::spawn(tee(stdout, tokio::io::stdout(), output)));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            let output = TeeOutput::new(log.clone(), prefix);
+            tees.push(tokio::spawn(tee(stderr, tokio::io::stderr(), output)));
+        }
+    }
+
+    let outcome = match supervision.timeout {
+        Some(limit) => match tokio::time::timeout(limit, child.wait()).await {
+            Ok(status) => Outcome::Exited(status?),
+            Err(_) => {
+                terminate(&mut child).await?;
+                Outcome::TimedOut
+            }
+        },
+        None => Outcome::Exited(child.wait().await?),
+    };
+
+    for task in tees {
+        task.await??;
+    }
+    if let Some(log) = log {
+        log.lock().await.flush().await?;
+    }
+
+    Ok(outcome)
+}
+
+/// Where a tee copies output besides the terminal, and how terminal lines start.
+struct TeeOutput {
+    log: Option<Arc<Mutex<tokio::fs::File>>>,
+    prefix: Option<String>,
+}
+
+impl TeeOutput {
+    fn new(log: Option<Arc<Mutex<tokio::fs::File>>>, prefix: Option<String>) -> Self {
+        Self { log, prefix }
+    }
+}
+
+/// Copy a child's output stream to a terminal stream and the log file.
+async fn tee(
+    source: impl AsyncRead + Unpin,
+    mut terminal: impl AsyncWrite + Unpin,
+    output: TeeOutput,
+) -> Result<()> {
+    let Some(prefix) = &output.prefix else {
+        let mut source = source;
+        let mut buf = vec![0u8; 8192];
+        loop {
+            let n = source.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            terminal.write_all(&buf[..n]).await?;
+            terminal.flush().await?;
+            if let Some(log) = &output.log {
+                log.lock().await.write_all(&buf[..n]).await?;
+            }
+        }
+    };
+
+    // Prefixed output goes line by line so interleaved processes stay readable
+    let mut source = BufReader::new(source);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if source.read_until(b'\n', &mut line).await? == 0 {
+            return Ok(());
+        }
+        if !line.ends_with(b"\n") {
+            line.push(b'\n');
+        }
+        let mut prefixed = prefix.as_bytes().to_vec();
+        prefixed.extend_from_slice(&line);
+        terminal.write_all(&prefixed).await?;
+        terminal.flush().await?;
+        if let Some(log) = &output.log {
+            log.lock().await.write_all(&line).await?;
+        }
+    }
+}
+
+/// Ask the process to stop (SIGTERM, so JVM shutdown hooks run), then kill it after
+/// a grace period.
+async fn terminate(child: &mut Child) -> Result<()> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: kill(2) has no memory-safety preconditions
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGTERM);
+        }
+        if tokio::time::timeout(KILL_GRACE_PERIOD, child.wait())
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+    }
+
+    child.kill().await?;
+    Ok(())
+}
+
+/// Parse a non-zero duration like `90s`, `30m`, `1h` or `500ms`; a bare number is
+/// seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{value}' (expected e.g. 90s, 30m, 1h)"))?;
+
+    if number == 0 {
+        return Err(format!(
+            "duration '{value}' must be greater than zero (expected e.g. 90s, 30m, 1h)"
+        ));
+    }
+
+    let secs = |factor: u64| {
+        number
+            .checked_mul(factor)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("duration '{value}' is too long"))
+    };
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "" | "s" => Ok(Duration::from_secs(number)),
+        "m" => secs(60),
+        "h" => secs(3600),
+        _ => Err(format!(
+            "invalid duration unit '{unit}' (expected ms, s, m or h)"
+        )),
+    }
+}
+
+/// Format a duration in the largest whole unit, e.g. `30m`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if duration.subsec_millis() != 0 || secs == 0 {
+        format!("{}ms", duration.as_millis())
+    } else if secs.is_multiple_of(3600) {
+        format!("{}h", secs / 3600)
+    } else if secs.is_multiple_of(60) {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_round_trip() {
+        for (text, secs) in [("90s", 90), ("30m", 1800), ("1h", 3600), ("45", 45)] {
+            assert_eq!(parse_duration(text), Ok(Duration::from_secs(secs)));
+        }
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert!(parse_duration("10d").is_err());
+        assert!(parse_duration("m").is_err());
+        for zero in ["0", "0s", "0ms", "00m"] {
+            let err = parse_duration(zero).unwrap_err();
+            assert!(err.contains("must be greater than zero"), "{err}");
+        }
+        assert!(parse_duration(&format!("{}h", u64::MAX / 60)).is_err());
+        assert!(parse_duration(&format!("{}m", u64::MAX)).is_err());
+        assert_eq!(format_duration(Duration::from_secs(1800)), "30m");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_millis(500)), "500ms");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeout_stops_the_process() {
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        let supervision = Supervision {
+            timeout: Some(Duration::from_millis(100)),
+            ..Supervision::default()
+        };
+        let outcome = run_supervised(cmd, &supervision).await.unwrap();
+        assert!(matches!(outcome, Outcome::TimedOut));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn log_file_captures_stdout_and_stderr() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = dir.path().join("run.log");
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2; exit 3"]);
+        let supervision = Supervision {
+            log_file: Some(log_file.clone()),
+            ..Supervision::default()
+        };
+        let outcome = run_supervised(cmd, &supervision).await.unwrap();
+        assert!(matches!(outcome, Outcome::Exited(status) if status.code() == Some(3)));
+
+        let log = std::fs::read_to_string(log_file).unwrap();
+        assert!(log.contains("out\n") && log.contains("err\n"), "{log}");
+    }
+
+    #[tokio::test]
+    async fn prefixed_output_keeps_log_unprefixed() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = dir.path().join("shard.log");
+        let log = Arc::new(Mutex::new(
+            tokio::fs::File::create(&log_file).await.unwrap(),
+        ));
+        let output = TeeOutput::new(Some(log.clone()), Some("[1/2] ".to_string()));
+
+        let mut terminal = Vec::new();
+        tee(&b"one\ntwo"[..], &mut terminal, output).await.unwrap();
+        log.lock().await.flush().await.unwrap();
+
+        assert_eq!(
+            String::from_utf8(terminal).unwrap(),
+            "[1/2] one\n[1/2] two\n"
+        );
+        assert_eq!(std::fs::read_to_string(log_file).unwrap(), "one\ntwo\n");
+    }
+}
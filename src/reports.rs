@@ -0,0 +1,295 @@
+//! Karate reports - failed scenarios from a run's JSON or JUnit XML reports.
+//!
+//! Karate writes one `<name>.karate-json.txt` per feature (with the feature path and
+//! per-scenario results) and, in JUnit mode, one `<name>.xml` per feature. JSON
+//! reports are preferred; JUnit only identifies features by dotted class name.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// A failed scenario, addressable as `feature:line`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FailedScenario {
+    pub feature: String,
+    pub line: u32,
+    pub name: String,
+}
+
+impl FailedScenario {
+    /// The `path/to/file.feature:LINE` argument that runs just this scenario.
+    pub fn selector(&self) -> String {
+        format!("{}:{}", self.feature, self.line)
+    }
+}
+
+/// A scenario's outcome in a report.
+struct ScenarioResult {
+    scenario: FailedScenario,
+    failed: bool,
+}
+
+/// Failed scenarios in a reports directory, sorted and de-duplicated (scenario
+/// outline examples share a line). A missing directory has none.
+pub fn failed_scenarios(dir: &Path) -> Result<Vec<FailedScenario>> {
+    let failed: BTreeSet<FailedScenario> = scenario_results(dir, None)?
+        .into_iter()
+        .filter(|r| r.failed)
+        .map(|r| r.scenario)
+        .collect();
+
+    // One entry per feature line, keeping the first scenario name
+    let mut seen = BTreeSet::new();
+    Ok(failed
+        .into_iter()
+        .filter(|s| seen.insert(s.selector()))
+        .collect())
+}
+
+/// Selectors of the scenarios that passed in reports modified at or after `since`, so
+/// reports left over from earlier runs don't count. A line passes only when none of
+/// its results (e.g. scenario outline examples) failed.
+pub fn passed_scenarios_since(dir: &Path, since: SystemTime) -> Result<BTreeSet<String>> {
+    let results = scenario_results(dir, Some(since))?;
+    let failed: BTreeSet<String> = results
+        .iter()
+        .filter(|r| r.failed)
+        .map(|r| r.scenario.selector())
+        .collect();
+    Ok(results
+        .iter()
+        .map(|r| r.scenario.selector())
+        .filter(|selector| !failed.contains(selector))
+        .collect())
+}
+
+/// Scenario results from the reports in a directory, optionally only those modified at
+/// or after `since`. A missing directory has none.
+fn scenario_results(dir: &Path, since: Option<SystemTime>) -> Result<Vec<ScenarioResult>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut json_reports = Vec::new();
+    let mut junit_reports = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if let Some(since) = since {
+            if entry.metadata()?.modified()? < since {
+                continue;
+            }
+        }
+        let path = entry.path();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if name.ends_with(".karate-json.txt") {
+            json_reports.push(path);
+        } else if name.ends_with(".xml") {
+            junit_reports.push(path);
+        }
+    }
+
+    let mut results = Vec::new();
+    if !json_reports.is_empty() {
+        for path in json_reports {
+            let content = std::fs::read_to_string(&path)?;
+            let value: Value = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse report {}", path.display()))?;
+            results.extend(results_in_json(&value));
+        }
+    } else {
+        for path in junit_reports {
+            results.extend(results_in_junit(&std::fs::read_to_string(&path)?));
+        }
+    }
+    Ok(results)
+}
+
+/// Scenario results from a feature's JSON report.
+fn results_in_json(report: &Value) -> Vec<ScenarioResult> {
+    // `prefixedPath` is `file:` or `classpath:` prefixed; file paths run as-is
+    let feature = report["prefixedPath"]
+        .as_str()
+        .map(|p| p.strip_prefix("file:").unwrap_or(p))
+        .or_else(|| report["relativePath"].as_str())
+        .unwrap_or_default();
+    if feature.is_empty() {
+        return Vec::new();
+    }
+
+    report["scenarioResults"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| {
+            Some(ScenarioResult {
+                scenario: FailedScenario {
+                    feature: feature.to_string(),
+                    line: s["line"].as_u64()? as u32,
+                    name: s["name"].as_str().unwrap_or_default().to_string(),
+                },
+                failed: s["failed"].as_bool().unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+/// Scenario results from a JUnit XML report. Test case names start with Karate's
+/// `[section:line]` reference, and class names are the feature path with dots.
+fn results_in_junit(xml: &str) -> Vec<ScenarioResult> {
+    let mut results = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find("<testcase") {
+        rest = &rest[start..];
+        let tag_end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..tag_end];
+        let body_end = if tag.ends_with('/') {
+            tag_end
+        } else {
+            rest.find("</testcase>").unwrap_or(rest.len())
+        };
+        let body = &rest[tag_end..body_end];
+        rest = &rest[body_end..];
+
+        let (Some(class), Some(name)) = (xml_attr(tag, "classname"), xml_attr(tag, "name")) else {
+            continue;
+        };
+        let Some(line) = ref_id_line(&name) else {
+            continue;
+        };
+        let title = name
+            .split_once(']')
+            .map(|(_, title)| title.trim())
+            .unwrap_or_default();
+        results.push(ScenarioResult {
+            scenario: FailedScenario {
+                feature: format!("{}.feature", class.replace('.', "/")),
+                line,
+                name: title.to_string(),
+            },
+            failed: body.contains("<failure") || body.contains("<error"),
+        });
+    }
+    results
+}
+
+/// Line from a Karate reference id such as `[1:12]` or `[2.3:40]`.
+fn ref_id_line(name: &str) -> Option<u32> {
+    let id = name.strip_prefix('[')?.split(']').next()?;
+    id.rsplit(':').next()?.parse().ok()
+}
+
+/// Value of an attribute in an XML start tag, unescaped.
+pub fn xml_attr(tag: &str, attr: &str) -> Option<String> {
+    let start = tag.find(&format!(" {attr}=\""))? + attr.len() + 3;
+    let end = start + tag[start..].find('"')?;
+    Some(
+        tag[start..end]
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_failed_scenarios_from_json_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = serde_json::json!({
+            "prefixedPath": "file:features/users.feature",
+            "relativePath": "features/users.feature",
+            "scenarioResults": [
+                { "line": 5, "name": "list users", "failed": false },
+                { "line": 12, "name": "create user", "failed": true },
+                { "line": 20, "name": "outline row 1", "failed": true },
+                { "line": 20, "name": "outline row 2", "failed": true }
+            ]
+        });
+        std::fs::write(
+            dir.path().join("features.users.karate-json.txt"),
+            report.to_string(),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("karate-summary-json.txt"), "{}").unwrap();
+
+        let failed = failed_scenarios(dir.path()).unwrap();
+        let selectors: Vec<String> = failed.iter().map(|s| s.selector()).collect();
+        assert_eq!(
+            selectors,
+            vec!["features/users.feature:12", "features/users.feature:20"]
+        );
+        assert_eq!(failed[0].name, "create user");
+    }
+
+    #[test]
+    fn falls_back_to_junit_reports() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuite name="features.users" tests="3" failures="1">
+  <testcase classname="features.users" name="[1:5] list users" time="0.1"/>
+  <testcase classname="features.users" name="[2:12] create &quot;admin&quot; user" time="0.2">
+    <failure message="match failed">...</failure>
+  </testcase>
+  <testcase classname="features.users" name="[3:20] delete user" time="0.1">
+    <system-out>ok</system-out>
+  </testcase>
+</testsuite>"#;
+        let failed: Vec<FailedScenario> = results_in_junit(xml)
+            .into_iter()
+            .filter(|r| r.failed)
+            .map(|r| r.scenario)
+            .collect();
+        assert_eq!(
+            failed,
+            vec![FailedScenario {
+                feature: "features/users.feature".to_string(),
+                line: 12,
+                name: "create \"admin\" user".to_string(),
+            }]
+        );
+        assert_eq!(ref_id_line("[2.3:40] outline"), Some(40));
+    }
+
+    #[test]
+    fn only_fresh_reports_count_as_passed() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = serde_json::json!({
+            "prefixedPath": "file:features/users.feature",
+            "scenarioResults": [
+                { "line": 5, "name": "list users", "failed": false },
+                { "line": 20, "name": "outline row 1", "failed": false },
+                { "line": 20, "name": "outline row 2", "failed": true }
+            ]
+        });
+        std::fs::write(
+            dir.path().join("features.users.karate-json.txt"),
+            report.to_string(),
+        )
+        .unwrap();
+
+        let passed = passed_scenarios_since(dir.path(), SystemTime::UNIX_EPOCH).unwrap();
+        assert_eq!(
+            passed.into_iter().collect::<Vec<_>>(),
+            vec!["features/users.feature:5"]
+        );
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        assert!(passed_scenarios_since(dir.path(), later)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn missing_reports_dir_has_no_failures() {
+        assert!(failed_scenarios(Path::new("/no/such/reports"))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+//! Container resources - cgroup limits and the JVM defaults derived from them.
+//!
+//! The JVM detects cgroup limits itself, but sizes the heap at 25% of the limit,
+//! which is too small for a test runner that is the only process in its container.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Heap size as a percentage of the container memory limit.
+const MAX_RAM_PERCENTAGE: &str = "75.0";
+
+/// cgroup v1 reports "no limit" as a huge page-aligned number.
+const CGROUP_V1_UNLIMITED: u64 = 1 << 60;
+
+/// JVM flags that set the maximum heap size.
+const HEAP_FLAGS: &[&str] = &[
+    "-Xmx",
+    "-XX:MaxHeapSize=",
+    "-XX:MaxRAM=",
+    "-XX:MaxRAMPercentage=",
+    "-XX:MaxRAMFraction=",
+];
+
+/// Environment variables the JVM reads options from.
+const JVM_OPTION_VARS: &[&str] = &["JAVA_TOOL_OPTIONS", "JDK_JAVA_OPTIONS"];
+
+/// Memory and CPU limits of the current cgroup, if any.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ContainerLimits {
+    /// Memory limit in bytes
+    pub memory_bytes: Option<u64>,
+    /// CPU quota in cores (may be fractional)
+    pub cpus: Option<f64>,
+}
+
+impl ContainerLimits {
+    /// Read the limits of the current process (Linux only).
+    pub fn detect() -> Self {
+        if cfg!(target_os = "linux") {
+            let proc_cgroup = std::fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+            detect_in(Path::new("/sys/fs/cgroup"), &proc_cgroup)
+        } else {
+            ContainerLimits::default()
+        }
+    }
+
+    /// Processor count for the JVM: the CPU quota rounded up.
+    pub fn processor_count(&self) -> Option<u32> {
+        self.cpus.map(|cpus| (cpus.ceil() as u32).max(1))
+    }
+}
+
+/// Resources a delegated JVM runs with, as reported by `karate doctor`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JvmResources {
+    pub limits: ContainerLimits,
+    /// Flags the launcher adds because no explicit setting was found
+    pub defaults: Vec<String>,
+}
+
+impl JvmResources {
+    pub fn resolve(jvm_args: &[String], env: &[(String, String)]) -> Self {
+        let limits = ContainerLimits::detect();
+        JvmResources {
+            limits,
+            defaults: jvm_defaults(&limits, jvm_args, env),
+        }
+    }
+}
+
+/// Default heap and CPU flags for the container limits, skipping any setting that's
+/// already made in `jvm_args` or in JVM option variables from `env`, the JVM's full
+/// environment (a variable listed twice takes its last value).
+pub fn jvm_defaults(
+    limits: &ContainerLimits,
+    jvm_args: &[String],
+    env: &[(String, String)],
+) -> Vec<String> {
+    let mut explicit: Vec<String> = jvm_args.to_vec();
+    for var in JVM_OPTION_VARS {
+        if let Some((_, value)) = env.iter().rfind(|(name, _)| name == var) {
+            explicit.extend(value.split_whitespace().map(str::to_string));
+        }
+    }
+    let is_set = |prefixes: &[&str]| {
+        explicit
+            .iter()
+            .any(|arg| prefixes.iter().any(|p| arg.starts_with(p)))
+    };
+
+    let mut defaults = Vec::new();
+    if limits.memory_bytes.is_some() && !is_set(HEAP_FLAGS) {
+        defaults.push(format!("-XX:MaxRAMPercentage={MAX_RAM_PERCENTAGE}"));
+    }
+    if let Some(count) = limits.processor_count() {
+        if !is_set(&["-XX:ActiveProcessorCount="]) {
+            defaults.push(format!("-XX:ActiveProcessorCount={count}"));
+        }
+    }
+    defaults
+}
+
+/// Read cgroup v2 (unified) or v1 limits below a cgroup mount point, for the groups
+/// listed in `proc_cgroup` (the contents of `/proc/self/cgroup`). A limit set on a
+/// parent group also applies, so the smallest one from the process's group up to the
+/// mount point wins.
+fn detect_in(root: &Path, proc_cgroup: &str) -> ContainerLimits {
+    let read = |path: PathBuf| std::fs::read_to_string(path).ok();
+    let groups = parse_proc_cgroup(proc_cgroup);
+
+    if root.join("cgroup.controllers").exists() {
+        // The unified hierarchy is the "0::<path>" line
+        let group = groups
+            .iter()
+            .find(|(controllers, _)| controllers.is_empty())
+            .map_or("/", |(_, path)| *path);
+        let dirs = group_dirs(root, group);
+        ContainerLimits {
+            memory_bytes: smallest(
+                dirs.iter().filter_map(|dir| {
+                    read(dir.join("memory.max")).and_then(|s| parse_v2_memory(&s))
+                }),
+            ),
+            cpus: smallest(
+                dirs.iter()
+                    .filter_map(|dir| read(dir.join("cpu.max")).and_then(|s| parse_v2_cpu(&s))),
+            ),
+        }
+    } else {
+        let group_of = |controller: &str| {
+            groups
+                .iter()
+                .find(|(controllers, _)| controllers.contains(&controller))
+                .map_or("/", |(_, path)| *path)
+        };
+        let memory_bytes = smallest(
+            group_dirs(&root.join("memory"), group_of("memory"))
+                .iter()
+                .filter_map(|dir| read(dir.join("memory.limit_in_bytes")))
+                .filter_map(|s| s.trim().parse::<u64>().ok())
+                .filter(|limit| *limit < CGROUP_V1_UNLIMITED),
+        );
+        let cpus = ["cpu", "cpu,cpuacct"].iter().find_map(|mount| {
+            smallest(
+                group_dirs(&root.join(mount), group_of("cpu"))
+                    .iter()
+                    .filter_map(|dir| {
+                        let quota = read(dir.join("cpu.cfs_quota_us"))?;
+                        let period = read(dir.join("cpu.cfs_period_us"))?;
+                        cpu_quota(quota.trim(), period.trim())
+                    }),
+            )
+        });
+        ContainerLimits { memory_bytes, cpus }
+    }
+}
+
+/// `/proc/self/cgroup` lines ("<id>:<controllers>:<path>") as controllers and path.
+/// The cgroup v2 line has no controllers.
+fn parse_proc_cgroup(content: &str) -> Vec<(Vec<&str>, &str)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let (_, controllers, path) = (parts.next()?, parts.next()?, parts.next()?);
+            let controllers = controllers.split(',').filter(|c| !c.is_empty()).collect();
+            Some((controllers, path))
+        })
+        .collect()
+}
+
+/// The group's directory under a mount point and each parent up to the mount point.
+/// A path that isn't inside the mount (as seen from another cgroup namespace) falls
+/// back to the mount point itself.
+fn group_dirs(mount: &Path, group: &str) -> Vec<PathBuf> {
+    let relative = Path::new(group.trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        return vec![mount.to_path_buf()];
+    }
+    mount
+        .join(relative)
+        .ancestors()
+        .take_while(|dir| dir.starts_with(mount))
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// The smallest of some limits.
+fn smallest<T: PartialOrd>(limits: impl Iterator<Item = T>) -> Option<T> {
+    limits.reduce(|a, b| if b < a { b } else { a })
+}
+
+/// `memory.max`: a byte count or "max".
+fn parse_v2_memory(content: &str) -> Option<u64> {
+    content.trim().parse().ok()
+}
+
+/// `cpu.max`: "<quota> <period>", where quota may be "max".
+fn parse_v2_cpu(content: &str) -> Option<f64> {
+    let mut parts = content.split_whitespace();
+    cpu_quota(parts.next()?, parts.next()?)
+}
+
+/// Cores from a CFS quota and period; a negative or "max" quota means no limit.
+fn cpu_quota(quota: &str, period: &str) -> Option<f64> {
+    let quota: f64 = quota.parse().ok().filter(|q| *q > 0.0)?;
+    let period: f64 = period.parse().ok().filter(|p| *p > 0.0)?;
+    Some(quota / period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cgroup(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn reads_cgroup_v2_limits() {
+        let root = cgroup(&[
+            ("cgroup.controllers", "cpu memory"),
+            ("memory.max", "2147483648\n"),
+            ("cpu.max", "150000 100000\n"),
+        ]);
+        let limits = detect_in(root.path(), "");
+        assert_eq!(limits.memory_bytes, Some(2147483648));
+        assert_eq!(limits.cpus, Some(1.5));
+        assert_eq!(limits.processor_count(), Some(2));
+
+        let root = cgroup(&[
+            ("cgroup.controllers", "cpu memory"),
+            ("memory.max", "max\n"),
+            ("cpu.max", "max 100000\n"),
+        ]);
+        assert_eq!(detect_in(root.path(), ""), ContainerLimits::default());
+    }
+
+    #[test]
+    fn reads_cgroup_v1_limits() {
+        let root = cgroup(&[
+            ("memory/memory.limit_in_bytes", "1073741824\n"),
+            ("cpu,cpuacct/cpu.cfs_quota_us", "50000\n"),
+            ("cpu,cpuacct/cpu.cfs_period_us", "100000\n"),
+        ]);
+        let limits = detect_in(root.path(), "");
+        assert_eq!(limits.memory_bytes, Some(1073741824));
+        assert_eq!(limits.processor_count(), Some(1));
+
+        let root = cgroup(&[
+            ("memory/memory.limit_in_bytes", "9223372036854771712\n"),
+            ("cpu/cpu.cfs_quota_us", "-1\n"),
+            ("cpu/cpu.cfs_period_us", "100000\n"),
+        ]);
+        assert_eq!(detect_in(root.path(), ""), ContainerLimits::default());
+    }
+
+    #[test]
+    fn reads_nested_cgroup_v2_group() {
+        let root = cgroup(&[
+            ("cgroup.controllers", "cpu memory"),
+            ("memory.max", "max\n"),
+            ("kubepods/memory.max", "4294967296\n"),
+            ("kubepods/pod1/memory.max", "max\n"),
+            ("kubepods/pod1/cpu.max", "200000 100000\n"),
+            ("other/memory.max", "1024\n"),
+        ]);
+        let limits = detect_in(root.path(), "0::/kubepods/pod1\n");
+        assert_eq!(limits.memory_bytes, Some(4294967296));
+        assert_eq!(limits.cpus, Some(2.0));
+
+        // A group outside this mount (another cgroup namespace) reads the mount point
+        assert_eq!(
+            detect_in(root.path(), "0::/../elsewhere\n"),
+            ContainerLimits::default()
+        );
+    }
+
+    #[test]
+    fn reads_nested_cgroup_v1_groups() {
+        let root = cgroup(&[
+            ("memory/memory.limit_in_bytes", "9223372036854771712\n"),
+            ("memory/docker/abc/memory.limit_in_bytes", "536870912\n"),
+            ("cpu,cpuacct/cpu.cfs_quota_us", "-1\n"),
+            ("cpu,cpuacct/cpu.cfs_period_us", "100000\n"),
+            ("cpu,cpuacct/docker/abc/cpu.cfs_quota_us", "300000\n"),
+            ("cpu,cpuacct/docker/abc/cpu.cfs_period_us", "100000\n"),
+        ]);
+        let proc_cgroup = "12:memory:/docker/abc\n4:cpu,cpuacct:/docker/abc\n1:name=systemd:/\n";
+        let limits = detect_in(root.path(), proc_cgroup);
+        assert_eq!(limits.memory_bytes, Some(536870912));
+        assert_eq!(limits.cpus, Some(3.0));
+    }
+
+    #[test]
+    fn defaults_skip_explicit_settings() {
+        let limits = ContainerLimits {
+            memory_bytes: Some(1 << 30),
+            cpus: Some(2.0),
+        };
+        assert_eq!(
+            jvm_defaults(&limits, &[], &[]),
+            vec!["-XX:MaxRAMPercentage=75.0", "-XX:ActiveProcessorCount=2"]
+        );
+        assert_eq!(
+            jvm_defaults(&limits, &["-Xmx512m".to_string()], &[]),
+            vec!["-XX:ActiveProcessorCount=2"]
+        );
+        let env = [(
+            "JAVA_TOOL_OPTIONS".to_string(),
+            "-XX:MaxRAMPercentage=50 -XX:ActiveProcessorCount=4".to_string(),
+        )];
+        assert!(jvm_defaults(&limits, &[], &env).is_empty());
+        let overridden = [
+            env[0].clone(),
+            ("JAVA_TOOL_OPTIONS".to_string(), String::new()),
+        ];
+        assert_eq!(jvm_defaults(&limits, &[], &overridden).len(), 2);
+        assert!(jvm_defaults(&ContainerLimits::default(), &[], &[]).is_empty());
+    }
+}
@@ -0,0 +1,1056 @@
+//! Sharded runs - `karate run --shards N` split across several JVMs.
+//!
+//! The launcher finds the `.feature` files under the run's paths, partitions them
+//! (by historical duration when known, otherwise by count) and runs one JVM per
+//! shard with its own output directory (`<output>/shards/<n>`). Output lines are
+//! prefixed with the shard number, and the shards' JUnit XML reports are merged into
+//! `<output>/shards/junit.xml`. Suite durations from that report are kept in
+//! `cache/feature-durations.json` to balance the next run.
+//!
+//! For CI matrix jobs, `--shard-index I --shard-total N` runs only the features whose
+//! project-relative path hashes to shard `I`, alone or combined with `--shards`.
+
+use crate::agents::ensure_agents;
+use crate::cli::OutputFormat;
+use crate::config::{load_merged_config, validate_config_files};
+use crate::crash::prepare_oom_marker;
+use crate::delegate::{
+    exit_code, java_command, launch, plan_launch, shell_join, Argfile, LaunchOptions, LaunchPlan,
+    RunOutcome,
+};
+use crate::error::ExitCode;
+use crate::history::record_run;
+use crate::platform::KaratePaths;
+use crate::process::{format_duration, run_supervised, Outcome, Supervision};
+use crate::reports::xml_attr;
+use anyhow::{bail, Context, Result};
+use console::style;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Instant, SystemTime};
+
+/// Karate output directory when the run doesn't pass `-o`.
+const DEFAULT_OUTPUT: &str = "target";
+
+/// Report format every shard must write so results can be merged.
+const JUNIT_FORMAT: &str = "junit:xml";
+
+/// Build output and dependency directories skipped when looking for features (Maven
+/// and Gradle copy test resources into them).
+pub const IGNORED_DIRS: &[&str] = &["target", "build", "node_modules"];
+
+/// Karate `run` options that take a value, so the value isn't mistaken for a path.
+const VALUE_OPTIONS: &[&str] = &[
+    "-t",
+    "--tags",
+    "-T",
+    "--threads",
+    "-o",
+    "--output",
+    "-f",
+    "--format",
+    "-e",
+    "--env",
+    "-n",
+    "--name",
+    "-g",
+    "--configdir",
+    "-H",
+    "--hook",
+];
+
+/// Launcher sharding options taken out of `run` arguments.
+#[derive(Debug, Default, PartialEq)]
+pub struct ShardOptions {
+    /// Parallel JVMs on this machine (--shards)
+    pub shards: Option<usize>,
+    /// This job's part of the features (--shard-index, --shard-total)
+    pub selection: Option<ShardSelection>,
+}
+
+/// One of `total` stable parts of the discovered features, numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardSelection {
+    pub index: usize,
+    pub total: usize,
+}
+
+/// Take `--shards N` and `--shard-index I --shard-total N` out of `run` arguments.
+/// Returns `None` for other commands and for runs without these options.
+pub fn take_shard_options(args: &[String]) -> Result<Option<(ShardOptions, Vec<String>)>> {
+    if args.first().map(String::as_str) != Some("run") {
+        return Ok(None);
+    }
+
+    let mut shards = None;
+    let mut index = None;
+    let mut total = None;
+    let mut rest = Vec::with_capacity(args.len());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), None),
+        };
+        let target = match name {
+            "--shards" => &mut shards,
+            "--shard-index" => &mut index,
+            "--shard-total" => &mut total,
+            _ => {
+                rest.push(arg.clone());
+                continue;
+            }
+        };
+        let value = inline_value
+            .or_else(|| iter.next().map(String::as_str))
+            .unwrap_or_default();
+        match value.parse::<usize>() {
+            Ok(n) if n > 0 => *target = Some(n),
+            _ => bail!("{} needs a positive number, got '{}'", name, value),
+        }
+    }
+
+    let selection = match (index, total) {
+        (Some(index), Some(total)) if index <= total => Some(ShardSelection { index, total }),
+        (Some(index), Some(total)) => {
+            bail!(
+                "--shard-index {} is out of range for --shard-total {}",
+                index,
+                total
+            )
+        }
+        (None, None) => None,
+        _ => bail!("--shard-index and --shard-total must be used together"),
+    };
+    if shards.is_none() && selection.is_none() {
+        return Ok(None);
+    }
+    Ok(Some((ShardOptions { shards, selection }, rest)))
+}
+
+/// Where feature paths are resolved from before they are hashed into shards.
+#[derive(Debug, Clone)]
+pub struct ShardRoot {
+    cwd: PathBuf,
+    project_dir: PathBuf,
+}
+
+impl ShardRoot {
+    /// Resolve paths against the current directory, relative to the project root.
+    pub fn current() -> Result<Self> {
+        Ok(ShardRoot {
+            cwd: std::env::current_dir().context("Could not determine current directory")?,
+            project_dir: KaratePaths::project_dir(),
+        })
+    }
+
+    /// The path a feature is hashed by: relative to the project root with `/`
+    /// separators, so every job agrees however it named the feature. Features outside
+    /// the project keep their absolute path.
+    pub fn key(&self, feature: &str) -> String {
+        let absolute = lexical_normalize(&self.cwd.join(feature.replace('\\', "/")));
+        let project_dir = lexical_normalize(&self.project_dir);
+        let key = absolute.strip_prefix(&project_dir).unwrap_or(&absolute);
+        key.to_string_lossy().replace('\\', "/")
+    }
+}
+
+/// Resolve `.` and `..` without touching the filesystem.
+fn lexical_normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// The shard (from 1) a feature belongs to: a hash of its [`ShardRoot::key`], so
+/// adding or removing a feature doesn't move any other feature between shards.
+pub fn shard_of(key: &str, total: usize) -> usize {
+    let digest = Sha256::digest(key.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(bytes) % total as u64) as usize + 1
+}
+
+/// The features in the selected shard, in discovery order.
+pub fn select_features(
+    features: &[String],
+    selection: ShardSelection,
+    root: &ShardRoot,
+) -> Vec<String> {
+    features
+        .iter()
+        .filter(|f| shard_of(&root.key(f), selection.total) == selection.index)
+        .cloned()
+        .collect()
+}
+
+/// `run` arguments split into feature paths and everything else.
+#[derive(Debug, Default, PartialEq)]
+struct RunArgs {
+    paths: Vec<String>,
+    /// Options passed to every shard, minus `-o` and `-f`
+    options: Vec<String>,
+    output: Option<String>,
+    format: Option<String>,
+}
+
+impl RunArgs {
+    /// The options as given to the run, including `-o` and `-f`.
+    fn karate_options(&self) -> Vec<String> {
+        let mut options = self.options.clone();
+        if let Some(output) = &self.output {
+            options.extend(["-o".to_string(), output.clone()]);
+        }
+        if let Some(format) = &self.format {
+            options.extend(["-f".to_string(), format.clone()]);
+        }
+        options
+    }
+}
+
+fn split_run_args(args: &[String]) -> RunArgs {
+    let mut run_args = RunArgs::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with('-') {
+            run_args.paths.push(arg.clone());
+            continue;
+        }
+
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let value = if VALUE_OPTIONS.contains(&name) {
+            inline_value.or_else(|| iter.next().cloned())
+        } else {
+            None
+        };
+
+        match name {
+            "-o" | "--output" => run_args.output = value,
+            "-f" | "--format" => run_args.format = value,
+            _ => {
+                run_args.options.push(name.to_string());
+                run_args.options.extend(value);
+            }
+        }
+    }
+    run_args
+}
+
+/// Report formats for a shard: the run's own, plus JUnit XML.
+fn shard_format(format: Option<&str>) -> String {
+    match format {
+        Some(format) if format.split(',').any(|f| f.trim() == JUNIT_FORMAT) => format.to_string(),
+        Some(format) => format!("{format},{JUNIT_FORMAT}"),
+        None => JUNIT_FORMAT.to_string(),
+    }
+}
+
+/// The output directory (`-o`/`--output`) set in `run` arguments, if any.
+pub fn run_output(args: &[String]) -> Option<String> {
+    split_run_args(args).output
+}
+
+/// Feature files for the run's paths: directories are searched recursively (skipping
+/// hidden and build output directories), while
+/// files, `file.feature:LINE` selectors and `classpath:` paths are kept as they are.
+pub fn discover_features(paths: &[String]) -> Result<Vec<String>> {
+    let mut features = Vec::new();
+    for path in paths {
+        let fs_path = Path::new(path);
+        if fs_path.is_dir() {
+            let mut found = Vec::new();
+            find_feature_files(fs_path, &mut found)?;
+            found.sort();
+            features.extend(found.iter().map(|p| p.to_string_lossy().to_string()));
+        } else if fs_path.is_file() || path.starts_with("classpath:") || has_line_selector(path) {
+            features.push(path.clone());
+        } else {
+            bail!("Feature path not found: {}", path);
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    features.retain(|f| seen.insert(f.clone()));
+    Ok(features)
+}
+
+fn find_feature_files(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            if IGNORED_DIRS.contains(&name.as_str()) {
+                continue;
+            }
+            find_feature_files(&path, found)?;
+        } else if path.extension().is_some_and(|e| e == "feature") {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Whether a path is a `file.feature:LINE` scenario selector.
+fn has_line_selector(path: &str) -> bool {
+    path.rsplit_once(':').is_some_and(|(file, line)| {
+        file.ends_with(".feature") && !line.is_empty() && line.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+/// Seconds each feature took in earlier sharded runs, keyed by JUnit suite name
+/// (the feature path with dots, relative to its source root).
+#[derive(Debug, Default)]
+struct FeatureDurations(BTreeMap<String, f64>);
+
+impl FeatureDurations {
+    fn path(paths: &KaratePaths) -> PathBuf {
+        paths.cache.join("feature-durations.json")
+    }
+
+    /// Load the cache; a missing or unreadable one just means no history.
+    fn load(paths: &KaratePaths) -> Self {
+        std::fs::read_to_string(Self::path(paths))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .map(Self)
+            .unwrap_or_default()
+    }
+
+    fn save(&self, paths: &KaratePaths) -> Result<()> {
+        std::fs::create_dir_all(&paths.cache)?;
+        std::fs::write(Self::path(paths), serde_json::to_string_pretty(&self.0)?)?;
+        Ok(())
+    }
+
+    /// Record the suite times from a JUnit report.
+    fn record(&mut self, junit: &str) {
+        for suite in testsuites(junit) {
+            let (Some(name), Some(time)) = (xml_attr(suite, "name"), xml_attr(suite, "time"))
+            else {
+                continue;
+            };
+            if let Ok(time) = time.parse::<f64>() {
+                self.0.insert(name, time);
+            }
+        }
+    }
+
+    /// Duration of a feature, matching its dotted path against suite names. The
+    /// longest matching name wins, so `a/users.feature` doesn't pick up `b.users`
+    /// when `a.users` is known.
+    fn get(&self, feature: &str) -> Option<f64> {
+        let path = feature.strip_prefix("classpath:").unwrap_or(feature);
+        let path = match path.rsplit_once(':') {
+            Some((file, _)) if has_line_selector(path) => file,
+            _ => path,
+        };
+        let dotted = path
+            .strip_suffix(".feature")
+            .unwrap_or(path)
+            .replace(['/', '\\'], ".");
+        self.0
+            .iter()
+            .filter(|(name, _)| dotted == **name || dotted.ends_with(&format!(".{name}")))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, time)| *time)
+    }
+}
+
+/// Split features into at most `shards` groups. With duration history, each feature
+/// goes to the currently shortest shard, longest first (unknown features count as the
+/// average); otherwise features are dealt out in turn.
+fn partition(features: &[String], shards: usize, durations: &FeatureDurations) -> Vec<Vec<String>> {
+    let shards = shards.min(features.len()).max(1);
+    let mut groups = vec![Vec::new(); shards];
+
+    let known: Vec<f64> = features.iter().filter_map(|f| durations.get(f)).collect();
+    if known.is_empty() {
+        for (i, feature) in features.iter().enumerate() {
+            groups[i % shards].push(feature.clone());
+        }
+        return groups;
+    }
+
+    let average = known.iter().sum::<f64>() / known.len() as f64;
+    let mut estimated: Vec<(&String, f64)> = features
+        .iter()
+        .map(|f| (f, durations.get(f).unwrap_or(average)))
+        .collect();
+    estimated.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut totals = vec![0.0_f64; shards];
+    for (feature, duration) in estimated {
+        let shortest = (0..shards)
+            .min_by(|&a, &b| totals[a].total_cmp(&totals[b]))
+            .unwrap_or_default();
+        totals[shortest] += duration;
+        groups[shortest].push(feature.clone());
+    }
+    for group in &mut groups {
+        group.sort();
+    }
+    groups
+}
+
+/// `<testsuite>` elements in a JUnit report, whether it's a single suite or wrapped
+/// in `<testsuites>`.
+fn testsuites(xml: &str) -> Vec<&str> {
+    let mut suites = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find("<testsuite") {
+        rest = &rest[start..];
+        let is_suite = rest[10..].starts_with([' ', '>', '\n', '\t', '\r']);
+        let tag_end = rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+        if !is_suite {
+            rest = &rest[tag_end..];
+            continue;
+        }
+        let end = if rest[..tag_end].ends_with("/>") {
+            tag_end
+        } else {
+            rest.find("</testsuite>")
+                .map(|i| i + "</testsuite>".len())
+                .unwrap_or(rest.len())
+        };
+        suites.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    suites
+}
+
+/// Merge JUnit reports into one `<testsuites>` document with summed totals.
+fn merge_junit(reports: &[String]) -> String {
+    let suites: Vec<&str> = reports.iter().flat_map(|r| testsuites(r)).collect();
+
+    let count = |attr: &str| -> u64 {
+        suites
+            .iter()
+            .filter_map(|s| xml_attr(s, attr)?.parse::<u64>().ok())
+            .sum()
+    };
+    let time: f64 = suites
+        .iter()
+        .filter_map(|s| xml_attr(s, "time")?.parse::<f64>().ok())
+        .sum();
+
+    let mut merged = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    merged.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        count("tests"),
+        count("failures"),
+        count("errors"),
+        count("skipped"),
+        time
+    ));
+    for suite in suites {
+        merged.push_str(suite);
+        merged.push('\n');
+    }
+    merged.push_str("</testsuites>\n");
+    merged
+}
+
+/// JUnit reports written by one shard.
+fn read_junit_reports(reports_dir: &Path) -> Result<Vec<String>> {
+    if !reports_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(reports_dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "xml"))
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .map(|p| std::fs::read_to_string(p).map_err(Into::into))
+        .collect()
+}
+
+/// `run.log` becomes `run.shard-2.log`, so shards don't overwrite each other's log.
+fn shard_log_file(log_file: &Path, index: usize) -> PathBuf {
+    let stem = log_file
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match log_file.extension() {
+        Some(ext) => format!("{stem}.shard-{index}.{}", ext.to_string_lossy()),
+        None => format!("{stem}.shard-{index}"),
+    };
+    log_file.with_file_name(name)
+}
+
+/// One shard's features and resolved command.
+#[derive(Serialize)]
+struct Shard {
+    index: usize,
+    features: Vec<String>,
+    output_dir: PathBuf,
+    #[serde(skip)]
+    plan: LaunchPlan,
+    #[serde(rename = "command")]
+    command_args: Vec<String>,
+    #[serde(skip)]
+    argfile: Option<Argfile>,
+}
+
+/// Run `run` arguments (without the sharding options): select this job's features,
+/// then run them in one JVM or split across `--shards` JVMs.
+pub async fn run_sharded(
+    args: Vec<String>,
+    sharding: ShardOptions,
+    options: &LaunchOptions,
+) -> Result<ExitCode> {
+    let run_args = split_run_args(&args[1..]);
+    if run_args.paths.is_empty() {
+        bail!("Sharding needs the feature files or directories to split, e.g. 'karate run --shards 4 src/test/features'");
+    }
+    let mut features = discover_features(&run_args.paths)?;
+    if features.is_empty() {
+        bail!("No .feature files found in {}", run_args.paths.join(", "));
+    }
+
+    if let Some(selection) = sharding.selection {
+        let discovered = features.len();
+        features = select_features(&features, selection, &ShardRoot::current()?);
+        eprintln!(
+            "{} Shard {}/{}: {} of {} feature(s)",
+            style("▶").cyan().bold(),
+            selection.index,
+            selection.total,
+            features.len(),
+            discovered
+        );
+        if features.is_empty() {
+            eprintln!("  {}", style("Nothing to run in this shard").dim());
+            return Ok(ExitCode::Success);
+        }
+    }
+
+    let Some(shards) = sharding.shards else {
+        let mut selected = vec!["run".to_string()];
+        selected.extend(features);
+        selected.extend(run_args.karate_options());
+        return Ok(exit_code(launch(selected, options).await?, &options.config));
+    };
+    run_parallel(&run_args, &features, shards, options).await
+}
+
+/// Run features split across `shards` JVMs and merge their JUnit reports.
+async fn run_parallel(
+    run_args: &RunArgs,
+    features: &[String],
+    shards: usize,
+    options: &LaunchOptions,
+) -> Result<ExitCode> {
+    if options.debug_port.is_some() {
+        bail!("--debug-jvm can't be combined with --shards: every shard would listen on the same port");
+    }
+
+    let paths = KaratePaths::new();
+    let config = load_merged_config(&options.config)?;
+    for issue in validate_config_files()? {
+        eprintln!("{} {}", style("warning:").yellow().bold(), issue);
+    }
+
+    let mut durations = FeatureDurations::load(&paths);
+    let groups = partition(features, shards, &durations);
+    let output = PathBuf::from(run_args.output.as_deref().unwrap_or(DEFAULT_OUTPUT));
+    let shard_root = output.join("shards");
+    let format = shard_format(run_args.format.as_deref());
+
+    let mut planned = Vec::new();
+    for (i, group) in groups.into_iter().enumerate() {
+        let output_dir = shard_root.join((i + 1).to_string());
+        let mut shard_args = vec!["run".to_string()];
+        shard_args.extend(group.iter().cloned());
+        shard_args.extend(run_args.options.iter().cloned());
+        shard_args.extend([
+            "-o".to_string(),
+            output_dir.to_string_lossy().to_string(),
+            "-f".to_string(),
+            format.clone(),
+        ]);
+        let plan = plan_launch(&config, &paths, &shard_args, &options.extra_classpath)?;
+        let (command_args, argfile) = plan.command_args(&paths.cache);
+        planned.push(Shard {
+            index: i + 1,
+            features: group,
+            output_dir,
+            plan,
+            command_args,
+            argfile,
+        });
+    }
+
+    if let Some(format) = options.dry_run {
+        print_dry_run(&planned, format)?;
+        return Ok(ExitCode::Success);
+    }
+
+    ensure_agents(&config, &paths).await?;
+
+    // Stale reports from an earlier sharded run must not end up in the merge
+    if shard_root.exists() {
+        std::fs::remove_dir_all(&shard_root)
+            .with_context(|| format!("Failed to clear {}", shard_root.display()))?;
+    }
+
+    let total = planned.len();
+    println!(
+        "{} Running {} feature(s) in {} shard(s)",
+        style("▶").cyan().bold(),
+        features.len(),
+        total
+    );
+
+    let started_at = SystemTime::now();
+    let started = Instant::now();
+    let mut tasks = Vec::new();
+    for shard in &planned {
+        if let Some(argfile) = &shard.argfile {
+            argfile.write()?;
+        }
+        if let Some(marker) = &shard.plan.oom_marker {
+            prepare_oom_marker(marker);
+        }
+        let mut cmd = java_command(&shard.plan, &shard.command_args);
+        cmd.stdin(Stdio::null());
+        let supervision = Supervision {
+            timeout: options.supervision.timeout,
+            log_file: options
+                .supervision
+                .log_file
+                .as_deref()
+                .map(|log| shard_log_file(log, shard.index)),
+            output_prefix: Some(format!(
+                "{} ",
+                style(format!("[{}/{}]", shard.index, total)).cyan()
+            )),
+        };
+        tasks.push(tokio::spawn(async move {
+            let shard_started = Instant::now();
+            let outcome = run_supervised(cmd, &supervision).await;
+            (outcome, shard_started.elapsed())
+        }));
+    }
+
+    let mut outcomes = Vec::new();
+    for task in tasks {
+        let (outcome, elapsed) = task.await?;
+        outcomes.push((outcome?, elapsed));
+    }
+    // Shards aren't diagnosed, so an OutOfMemoryError marker is only clutter
+    for marker in planned.iter().filter_map(|s| s.plan.oom_marker.as_ref()) {
+        let _ = std::fs::remove_file(marker);
+    }
+
+    // Merge the shards' JUnit reports and remember suite times for the next split
+    let mut reports = Vec::new();
+    for shard in &planned {
+        reports.extend(read_junit_reports(
+            &shard.output_dir.join("karate-reports"),
+        )?);
+    }
+    let merged_path = shard_root.join("junit.xml");
+    if !reports.is_empty() {
+        let merged = merge_junit(&reports);
+        std::fs::create_dir_all(&shard_root)?;
+        std::fs::write(&merged_path, &merged)?;
+        durations.record(&merged);
+        if let Err(e) = durations.save(&paths) {
+            eprintln!(
+                "{} Could not save feature durations: {}",
+                style("warning:").yellow().bold(),
+                e
+            );
+        }
+    }
+
+    println!();
+    println!("{} Shard Summary", style("▶").cyan().bold());
+    println!();
+    let mut timed_out = false;
+    let mut signal = None;
+    let mut worst = 0;
+    for (shard, (outcome, elapsed)) in planned.iter().zip(&outcomes) {
+        let elapsed = format!("{:.1}s", elapsed.as_secs_f64());
+        let (mark, result) = match outcome {
+            Outcome::Exited(status) => match RunOutcome::from_status(status) {
+                RunOutcome::Signaled(killed_by) => {
+                    signal = signal.or(Some(killed_by));
+                    (style("✗").red(), format!("killed by signal {killed_by}"))
+                }
+                _ => {
+                    let code = status.code().unwrap_or(1);
+                    worst = worst.max(code);
+                    let mark = if code == 0 {
+                        style("✓").green()
+                    } else {
+                        style("✗").red()
+                    };
+                    (mark, format!("exit {code}"))
+                }
+            },
+            Outcome::TimedOut => {
+                timed_out = true;
+                let limit = options.supervision.timeout.unwrap_or_default();
+                (
+                    style("✗").red(),
+                    format!("timed out after {}", format_duration(limit)),
+                )
+            }
+        };
+        println!(
+            "  {} shard {}: {} feature(s), {} {}",
+            mark,
+            shard.index,
+            shard.features.len(),
+            result,
+            style(format!("({elapsed})")).dim()
+        );
+    }
+    println!();
+    println!("  Total time: {:.1}s", started.elapsed().as_secs_f64());
+    if reports.is_empty() {
+        println!("  {}", style("No JUnit reports were written").dim());
+    } else {
+        println!("  JUnit report: {}", merged_path.display());
+    }
+
+    // A timeout outranks a killed JVM, which outranks test failures; otherwise the
+    // highest exit code wins
+    let outcome = match (timed_out, signal) {
+        (true, _) => RunOutcome::TimedOut,
+        (false, Some(signal)) => RunOutcome::Signaled(signal),
+        (false, None) => RunOutcome::Exited(worst),
+    };
+
+    // History replays the whole sharded run on the same features
+    let mut history_args = vec![
+        "run".to_string(),
+        "--shards".to_string(),
+        shards.to_string(),
+    ];
+    history_args.extend(features.iter().cloned());
+    history_args.extend(run_args.karate_options());
+    record_run(
+        &paths,
+        &history_args,
+        options,
+        &planned[0].plan,
+        started_at,
+        started.elapsed(),
+        outcome,
+    );
+
+    Ok(exit_code(outcome, &options.config))
+}
+
+/// Print each shard's features and command for --dry-run.
+fn print_dry_run(shards: &[Shard], format: OutputFormat) -> Result<()> {
+    let commands: Vec<Vec<String>> = shards
+        .iter()
+        .map(|shard| {
+            std::iter::once(shard.plan.java.to_string_lossy().to_string())
+                .chain(shard.command_args.iter().cloned())
+                .collect()
+        })
+        .collect();
+
+    if format == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct DryRunShard<'a> {
+            #[serde(flatten)]
+            shard: &'a Shard,
+            command: &'a [String],
+        }
+        let dry_run: Vec<DryRunShard> = shards
+            .iter()
+            .zip(&commands)
+            .map(|(shard, command)| DryRunShard { shard, command })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&dry_run)?);
+        return Ok(());
+    }
+
+    println!("{} Resolved shards (dry run)", style("▶").cyan().bold());
+    for (shard, command) in shards.iter().zip(&commands) {
+        println!();
+        println!(
+            "{}",
+            style(format!("Shard {}/{}", shard.index, shards.len()))
+                .bold()
+                .underlined()
+        );
+        for feature in &shard.features {
+            println!("  {}", feature);
+        }
+        println!("  Output:  {}", shard.output_dir.display());
+        println!("  Command: {}", shell_join(command));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn takes_shard_options_from_run_only() {
+        let args = strings(&["run", "--shards", "4", "features", "-t", "@smoke"]);
+        let (sharding, rest) = take_shard_options(&args).unwrap().unwrap();
+        assert_eq!(sharding.shards, Some(4));
+        assert_eq!(rest, strings(&["run", "features", "-t", "@smoke"]));
+
+        let args = strings(&["run", "--shard-index=2", "--shard-total", "3", "a.feature"]);
+        let (sharding, rest) = take_shard_options(&args).unwrap().unwrap();
+        assert_eq!(
+            sharding,
+            ShardOptions {
+                shards: None,
+                selection: Some(ShardSelection { index: 2, total: 3 }),
+            }
+        );
+        assert_eq!(rest, strings(&["run", "a.feature"]));
+
+        assert_eq!(
+            take_shard_options(&strings(&["run", "features"])).unwrap(),
+            None
+        );
+        assert_eq!(
+            take_shard_options(&strings(&["mock", "--shards", "2"])).unwrap(),
+            None
+        );
+        assert!(take_shard_options(&strings(&["run", "--shards", "0"])).is_err());
+        assert!(take_shard_options(&strings(&["run", "--shard-index", "1"])).is_err());
+        assert!(take_shard_options(&strings(&[
+            "run",
+            "--shard-index",
+            "4",
+            "--shard-total",
+            "3"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn shard_selection_is_stable() {
+        let features: Vec<String> = (0..40).map(|i| format!("features/f{i}.feature")).collect();
+        let root = test_root();
+        let shards: Vec<Vec<String>> = (1..=3)
+            .map(|index| select_features(&features, ShardSelection { index, total: 3 }, &root))
+            .collect();
+        assert_eq!(shards.iter().map(Vec::len).sum::<usize>(), features.len());
+        assert!(shards.iter().all(|s| !s.is_empty()));
+
+        // A new feature doesn't move existing ones
+        let mut more = features.clone();
+        more.push("features/new.feature".to_string());
+        let selected = select_features(&more, ShardSelection { index: 1, total: 3 }, &root);
+        assert!(shards[0].iter().all(|f| selected.contains(f)));
+    }
+
+    fn test_root() -> ShardRoot {
+        ShardRoot {
+            cwd: PathBuf::from("/repo/module"),
+            project_dir: PathBuf::from("/repo"),
+        }
+    }
+
+    #[test]
+    fn shard_keys_are_relative_to_the_project() {
+        let root = test_root();
+        for feature in [
+            "features/a.feature",
+            "./features\\a.feature",
+            "/repo/module/features/a.feature",
+            "../module/./features/a.feature",
+        ] {
+            assert_eq!(root.key(feature), "module/features/a.feature", "{feature}");
+        }
+
+        // The same feature named from the project root lands in the same shard
+        let from_root = ShardRoot {
+            cwd: PathBuf::from("/repo"),
+            ..test_root()
+        };
+        assert_eq!(
+            from_root.key("module/features/a.feature"),
+            root.key("features/a.feature")
+        );
+        assert_eq!(root.key("/elsewhere/b.feature"), "/elsewhere/b.feature");
+    }
+
+    #[test]
+    fn splits_paths_from_options() {
+        let args = strings(&[
+            "features",
+            "-t",
+            "@smoke",
+            "-o",
+            "build",
+            "--format=json",
+            "-H",
+            "com.example.Hook",
+            "--hook",
+            "com.example.Other",
+            "-D",
+            "more",
+        ]);
+        assert_eq!(
+            split_run_args(&args),
+            RunArgs {
+                paths: strings(&["features", "more"]),
+                options: strings(&[
+                    "-t",
+                    "@smoke",
+                    "-H",
+                    "com.example.Hook",
+                    "--hook",
+                    "com.example.Other",
+                    "-D",
+                ]),
+                output: Some("build".to_string()),
+                format: Some("json".to_string()),
+            }
+        );
+        assert_eq!(shard_format(Some("json")), "json,junit:xml");
+        assert_eq!(shard_format(Some("junit:xml")), "junit:xml");
+    }
+
+    #[test]
+    fn discovers_features_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("b/nested")).unwrap();
+        std::fs::create_dir_all(root.join(".hidden")).unwrap();
+        for file in [
+            "a.feature",
+            "b/nested/c.feature",
+            "b/notes.txt",
+            ".hidden/d.feature",
+        ] {
+            std::fs::write(root.join(file), "Feature:").unwrap();
+        }
+
+        let root_arg = root.to_string_lossy().to_string();
+        let features = discover_features(&[root_arg, "x.feature:12".to_string()]).unwrap();
+        let relative: Vec<String> = features
+            .iter()
+            .map(|f| {
+                f.strip_prefix(&*root.to_string_lossy())
+                    .unwrap_or(f)
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            relative,
+            vec!["/a.feature", "/b/nested/c.feature", "x.feature:12"]
+        );
+        assert!(discover_features(&["/no/such/dir".to_string()]).is_err());
+    }
+
+    #[test]
+    fn partitions_by_count_without_history() {
+        let features = strings(&["a.feature", "b.feature", "c.feature"]);
+        let groups = partition(&features, 2, &FeatureDurations::default());
+        assert_eq!(
+            groups,
+            vec![
+                strings(&["a.feature", "c.feature"]),
+                strings(&["b.feature"])
+            ]
+        );
+        assert_eq!(
+            partition(&features, 5, &FeatureDurations::default()).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn partitions_by_recorded_duration() {
+        let mut durations = FeatureDurations::default();
+        durations.record(
+            r#"<testsuites>
+  <testsuite name="api.slow" tests="1" time="30.0"></testsuite>
+  <testsuite name="api.medium" tests="1" time="20.0"/>
+  <testsuite name="api.quick" tests="1" time="5.0"></testsuite>
+</testsuites>"#,
+        );
+        assert_eq!(durations.get("src/test/java/api/slow.feature"), Some(30.0));
+        assert_eq!(durations.get("classpath:api/quick.feature:7"), Some(5.0));
+        assert_eq!(durations.get("other/unknown.feature"), None);
+
+        let features = strings(&[
+            "src/api/medium.feature",
+            "src/api/quick.feature",
+            "src/api/slow.feature",
+            "src/api/new.feature",
+        ]);
+        let groups = partition(&features, 2, &durations);
+        assert_eq!(
+            groups,
+            vec![
+                strings(&["src/api/quick.feature", "src/api/slow.feature"]),
+                strings(&["src/api/medium.feature", "src/api/new.feature"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_junit_reports() {
+        let first = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuite name="api.users" tests="2" failures="1" errors="0" skipped="0" time="1.5">
+  <testcase classname="api.users" name="[1:5] list" time="0.5"/>
+</testsuite>"#
+            .to_string();
+        let second = r#"<?xml version="1.0"?>
+<testsuites><testsuite name="api.orders" tests="3" failures="0" errors="1" time="2.25"></testsuite></testsuites>"#
+            .to_string();
+
+        let merged = merge_junit(&[first, second]);
+        assert!(merged.contains(
+            r#"<testsuites tests="5" failures="1" errors="1" skipped="0" time="3.750">"#
+        ));
+        assert_eq!(testsuites(&merged).len(), 2);
+        assert_eq!(merged.matches("<?xml").count(), 1);
+    }
+
+    #[test]
+    fn shard_log_files_are_numbered() {
+        assert_eq!(
+            shard_log_file(Path::new("logs/run.log"), 2),
+            PathBuf::from("logs/run.shard-2.log")
+        );
+        assert_eq!(
+            shard_log_file(Path::new("run"), 1),
+            PathBuf::from("run.shard-1")
+        );
+    }
+}